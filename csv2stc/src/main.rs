@@ -0,0 +1,116 @@
+use std::{
+    ffi::OsStr,
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+fn colored_println<D>(prefix: &str, color: termcolor::Color, message: D)
+where
+    D: Display,
+{
+    let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    stdout
+        .set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))
+        .expect("failed to set text colour");
+    write!(&mut stdout, "{} ", prefix).expect("failed to write to stdout");
+
+    stdout.reset().expect("failed to reset text color");
+    writeln!(&mut stdout, "{}", message).expect("failed to write to stdout");
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = pico_args::Arguments::from_env();
+    let delete = args.contains("--del");
+    let out_dir: Option<PathBuf> = args.opt_value_from_str("--out")?;
+    let files = args.finish();
+    if files.is_empty() {
+        println!("Usage: [--out dir] [--del] files");
+        println!("Options:");
+        println!("    --out    Directory to write tables to, defaults to the directory of input file");
+        println!("    --del    Delete input file after processing");
+        return Ok(());
+    }
+
+    if let Some(dir) = &out_dir {
+        fs::create_dir_all(dir)?;
+    }
+
+    for path in files.iter().map(PathBuf::from) {
+        if !path.exists() || !path.is_file() {
+            colored_println("Skipping", Color::Yellow, path.display());
+            continue;
+        }
+
+        let table_id = match path.extension().and_then(OsStr::to_str) {
+            Some("csv") => table_id(&path),
+            _ => continue,
+        };
+
+        let table_id = match table_id {
+            Some(id) => id,
+            None => {
+                colored_println("Skipping", Color::Yellow, format!("{}, no table id in the file name", path.display()));
+                continue;
+            }
+        };
+
+        let out_path = match &out_dir {
+            Some(dir) => dir.join(format!("{}.stc", table_id)),
+            None => path.with_file_name(format!("{}.stc", table_id)),
+        };
+
+        csv_to_stc(&path, &out_path, table_id);
+
+        if delete {
+            colored_println("Deleting", Color::Red, path.display());
+            fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Table id from `<id>_<name>.csv` or `<id>.csv`
+fn table_id(path: &Path) -> Option<u16> {
+    let stem = path.file_stem()?.to_str()?;
+    let id = stem.split('_').next()?;
+    id.parse().ok()
+}
+
+fn csv_to_stc<P>(in_path: P, out_path: P, table_id: u16)
+where
+    P: AsRef<Path>,
+{
+    let in_path = in_path.as_ref();
+    let file = fs::File::open(in_path).expect("failed to open csv file");
+    let mut file = io::BufReader::new(file);
+
+    // skip column names
+    let mut names = String::new();
+    let read = file.read_line(&mut names).expect("failed to read column names");
+
+    let mut table = if read == 0 {
+        colored_println("   Empty", Color::Cyan, in_path.display());
+        stc::Table::new(table_id)
+    } else {
+        colored_println(" Parsing", Color::Green, in_path.display());
+        stc::Table::from_csv(table_id, file).expect("failed to parse csv table")
+    };
+
+    // undo escaping of new lines done by stc2csv
+    table.unescape_strings();
+
+    let out = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(out_path)
+        .expect("failed to open file for writing");
+    let mut out = io::BufWriter::new(out);
+
+    table.serialize(&mut out).expect("failed to serialize stc table");
+}
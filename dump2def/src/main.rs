@@ -0,0 +1,44 @@
+use std::{fs, path::PathBuf};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = pico_args::Arguments::from_env();
+    let region: Option<String> = args.opt_value_from_str("--region")?;
+    let version: Option<String> = args.opt_value_from_str("--version")?;
+    let shift: Option<u16> = args.opt_value_from_str("--shift")?;
+    let mut files = args.finish().into_iter().map(PathBuf::from);
+    let (dump_path, out_path) = match (files.next(), files.next()) {
+        (Some(dump), Some(out)) => (dump, out),
+        _ => {
+            println!("Usage: [--region region] [--version version] [--shift index] dump output");
+            println!("Options:");
+            println!("    --region    Region written in the header, UNKNOWN_REGION by default");
+            println!("    --version   Version written in the header, UNKNOWN_VERSION by default");
+            println!("    --shift     Shift table IDs by 1, starting with the table at index");
+            return Ok(());
+        }
+    };
+
+    let dump = fs::read_to_string(&dump_path)?;
+    let tables = match stc::definitions::from_il2cpp_dump(&dump, shift) {
+        Ok(tables) => tables,
+        Err(err) => {
+            eprintln!("Failed to parse {}: {}", dump_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let file = stc::definitions::DefinitionsFile {
+        region: Some(region.unwrap_or_else(|| "UNKNOWN_REGION".to_owned())),
+        version: Some(version.unwrap_or_else(|| "UNKNOWN_VERSION".to_owned())),
+        tables,
+    };
+    fs::write(&out_path, file.to_string())?;
+
+    println!(
+        "Written {} tables to {}",
+        file.tables.len(),
+        out_path.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! Tables packed into the `stc_data.dat` archive the client downloads
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufReader, Read, Seek, Write},
+    path::Path,
+};
+
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{header, DeserializeOptions, Error, Table};
+
+/// `stc_<version><md5>.zip` archive, stored by the client as `stc_data.dat`
+pub struct Archive<R> {
+    zip: ZipArchive<R>,
+    // entry names, in the order of the archive
+    names: Vec<String>,
+}
+
+impl Archive<BufReader<fs::File>> {
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::new(BufReader::new(fs::File::open(path)?))
+    }
+}
+
+impl<R> Archive<R>
+where
+    R: Read + Seek,
+{
+    pub fn new(reader: R) -> Result<Self, Error> {
+        let mut zip = ZipArchive::new(reader)?;
+
+        let mut names = Vec::with_capacity(zip.len());
+        for i in 0..zip.len() {
+            names.push(zip.by_index_raw(i)?.name().to_owned());
+        }
+
+        Ok(Self { zip, names })
+    }
+
+    /// Names of every entry, in the order of the archive
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Names of the `.stc` entries, in the order of the archive
+    pub fn table_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .filter(|name| name.ends_with(".stc"))
+    }
+
+    /// Decompressed contents of the entry
+    pub fn read(&mut self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut entry = self.zip.by_name(name)?;
+
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    pub fn read_table(&mut self, name: &str) -> Result<Table, Error> {
+        self.read_table_with(name, &DeserializeOptions::default())
+    }
+
+    pub fn read_table_with(
+        &mut self,
+        name: &str,
+        options: &DeserializeOptions,
+    ) -> Result<Table, Error> {
+        let bytes = self.read(name)?;
+        Table::deserialize_with(&mut io::Cursor::new(bytes), options)
+    }
+
+    /// Table id of the entry, `None` if it's not a table
+    fn table_id(&mut self, index: usize) -> Result<Option<u16>, Error> {
+        let name = &self.names[index];
+        if !name.ends_with(".stc") {
+            return Ok(None);
+        }
+
+        let zip = &mut self.zip;
+        header::table_id(Path::new(name), || Ok(zip.by_index(index)?)).map(Some)
+    }
+
+    /// Write a copy of the archive into `writer`, with the tables replacing the entries of the same id
+    ///
+    /// Entries keep their names, order and compression, the rest of them are copied byte-for-byte.
+    /// Tables without an entry in the archive are an error, as the client wouldn't pick them up,
+    /// so are several tables with the same id.
+    pub fn repack<'t, W, I>(&mut self, writer: W, tables: I) -> Result<W, Error>
+    where
+        W: Write + Seek,
+        I: IntoIterator<Item = &'t Table>,
+    {
+        let mut by_id: HashMap<u16, &Table> = HashMap::new();
+        for table in tables {
+            if by_id.insert(table.id, table).is_some() {
+                return Err(Error::DuplicateTableId(table.id));
+            }
+        }
+
+        let mut replacements = Vec::with_capacity(self.names.len());
+        for index in 0..self.names.len() {
+            let table = match self.table_id(index)? {
+                Some(id) => by_id.remove(&id),
+                None => None,
+            };
+            replacements.push(table);
+        }
+
+        // check before writing anything
+        if let Some(id) = by_id.keys().min() {
+            return Err(Error::TableNotInArchive(*id));
+        }
+
+        let mut zip = ZipWriter::new(writer);
+        zip.set_raw_comment(self.zip.comment().to_vec());
+
+        for (index, table) in replacements.into_iter().enumerate() {
+            let entry = self.zip.by_index_raw(index)?;
+
+            let table = match table {
+                Some(table) => table,
+                None => {
+                    zip.raw_copy_file(entry)?;
+                    continue;
+                }
+            };
+
+            let mut options = FileOptions::default()
+                .compression_method(entry.compression())
+                .last_modified_time(entry.last_modified());
+            if let Some(mode) = entry.unix_mode() {
+                options = options.unix_permissions(mode);
+            }
+            let name = entry.name().to_owned();
+
+            let mut buffer = io::Cursor::new(Vec::new());
+            table.serialize(&mut buffer)?;
+
+            zip.start_file(name, options)?;
+            zip.write_all(buffer.get_ref())?;
+        }
+
+        Ok(zip.finish()?)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.zip.into_inner()
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_archive() -> Vec<u8> {
+    use zip::CompressionMethod;
+
+    use crate::Value;
+
+    let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for id in [5001, 5000] {
+        let mut table = Table::new(id);
+        table
+            .add_row(vec![Value::I32(1), Value::String(format!("table {}", id))])
+            .unwrap();
+        let mut buffer = io::Cursor::new(Vec::new());
+        table.serialize(&mut buffer).unwrap();
+
+        zip.start_file(format!("{}.stc", id), options).unwrap();
+        zip.write_all(buffer.get_ref()).unwrap();
+    }
+    zip.start_file("version.txt", options).unwrap();
+    zip.write_all(b"3.0").unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn archive() {
+    use crate::Value;
+
+    let mut archive = Archive::new(io::Cursor::new(test_archive())).unwrap();
+    assert_eq!(archive.names(), ["5001.stc", "5000.stc", "version.txt"]);
+    assert_eq!(
+        archive.table_names().collect::<Vec<_>>(),
+        ["5001.stc", "5000.stc"]
+    );
+
+    let table = archive.read_table("5000.stc").unwrap();
+    assert_eq!(table.id, 5000);
+    assert_eq!(
+        table.rows[0],
+        [Value::I32(1), Value::String("table 5000".into())]
+    );
+    assert_eq!(archive.read("version.txt").unwrap(), b"3.0");
+
+    assert!(matches!(archive.read("5002.stc"), Err(Error::Zip(_))));
+    assert!(archive.read_table("version.txt").is_err());
+}
+
+#[test]
+fn repack() {
+    use zip::CompressionMethod;
+
+    use crate::Value;
+
+    let original = test_archive();
+    let mut archive = Archive::new(io::Cursor::new(original.clone())).unwrap();
+
+    let mut table = archive.read_table("5000.stc").unwrap();
+    table.rows[0][1] = Value::String("edited".into());
+
+    let bytes = archive
+        .repack(io::Cursor::new(Vec::new()), [&table])
+        .unwrap()
+        .into_inner();
+
+    let mut repacked = Archive::new(io::Cursor::new(bytes.clone())).unwrap();
+    assert_eq!(repacked.names(), archive.names());
+    assert_eq!(
+        repacked.read_table("5000.stc").unwrap().rows[0][1],
+        Value::String("edited".into())
+    );
+    assert_eq!(
+        repacked.zip.by_name("5000.stc").unwrap().compression(),
+        CompressionMethod::Deflated
+    );
+
+    // untouched entries are copied as is
+    let raw = |archive: &mut Archive<_>, bytes: &[u8], name| {
+        let entry = archive.zip.by_name(name).unwrap();
+        let start = entry.data_start() as usize;
+        let end = start + entry.compressed_size() as usize;
+        (entry.crc32(), bytes[start..end].to_vec())
+    };
+    for name in ["5001.stc", "version.txt"] {
+        assert_eq!(
+            raw(&mut archive, &original, name),
+            raw(&mut repacked, &bytes, name)
+        );
+    }
+
+    assert!(matches!(
+        archive.repack(io::Cursor::new(Vec::new()), [&table, &table]),
+        Err(Error::DuplicateTableId(5000))
+    ));
+
+    table.id = 5002;
+    assert!(matches!(
+        archive.repack(io::Cursor::new(Vec::new()), [&table]),
+        Err(Error::TableNotInArchive(5002))
+    ));
+}
@@ -0,0 +1,519 @@
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt, io, str,
+    sync::OnceLock,
+};
+
+use crate::{
+    reader::BLOCK_SIZE,
+    value::{impl_as, InvalidType},
+    Error, Schema, Table, TableHeader, Value,
+};
+
+/// Value borrowed from the underlying buffer, see [`Value`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRef<'a> {
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(&'a str),
+    /// String that is not valid UTF-8
+    Bytes(&'a [u8]),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Decode the value at `pos`, advancing it past the value
+    fn read(column_type: u8, data: &'a [u8], pos: &mut usize) -> Result<Self, Error> {
+        let value = match column_type {
+            1 => ValueRef::I8(i8::from_le_bytes(take(data, pos)?)),
+            2 => ValueRef::U8(u8::from_le_bytes(take(data, pos)?)),
+            3 => ValueRef::I16(i16::from_le_bytes(take(data, pos)?)),
+            4 => ValueRef::U16(u16::from_le_bytes(take(data, pos)?)),
+            5 => ValueRef::I32(i32::from_le_bytes(take(data, pos)?)),
+            6 => ValueRef::U32(u32::from_le_bytes(take(data, pos)?)),
+            7 => ValueRef::I64(i64::from_le_bytes(take(data, pos)?)),
+            8 => ValueRef::U64(u64::from_le_bytes(take(data, pos)?)),
+            9 => ValueRef::F32(f32::from_le_bytes(take(data, pos)?)),
+            10 => ValueRef::F64(f64::from_le_bytes(take(data, pos)?)),
+            11 => {
+                let [_is_ascii] = take(data, pos)?;
+                let len = u16::from_le_bytes(take(data, pos)?);
+                let bytes = take_slice(data, pos, len.into())?;
+
+                match str::from_utf8(bytes) {
+                    Ok(string) => ValueRef::String(string),
+                    Err(_) => ValueRef::Bytes(bytes),
+                }
+            }
+            unknown => return Err(Error::UnknownColumnType(unknown)),
+        };
+
+        Ok(value)
+    }
+
+    /// Advance `pos` past the value without decoding it
+    fn skip(column_type: u8, data: &[u8], pos: &mut usize) -> Result<(), Error> {
+        let len = match column_type {
+            1 | 2 => 1,
+            3 | 4 => 2,
+            5 | 6 | 9 => 4,
+            7 | 8 | 10 => 8,
+            11 => {
+                let [_is_ascii] = take(data, pos)?;
+                u16::from_le_bytes(take(data, pos)?).into()
+            }
+            unknown => return Err(Error::UnknownColumnType(unknown)),
+        };
+        take_slice(data, pos, len)?;
+
+        Ok(())
+    }
+
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ValueRef::I8(v) => Value::I8(v),
+            ValueRef::U8(v) => Value::U8(v),
+            ValueRef::I16(v) => Value::I16(v),
+            ValueRef::U16(v) => Value::U16(v),
+            ValueRef::I32(v) => Value::I32(v),
+            ValueRef::U32(v) => Value::U32(v),
+            ValueRef::I64(v) => Value::I64(v),
+            ValueRef::U64(v) => Value::U64(v),
+            ValueRef::F32(v) => Value::F32(v),
+            ValueRef::F64(v) => Value::F64(v),
+            ValueRef::String(v) => Value::String(v.to_owned()),
+            ValueRef::Bytes(v) => Value::String(String::from_utf8_lossy(v).into_owned()),
+        }
+    }
+
+    impl_as!(as_i8 -> i8, I8);
+    impl_as!(as_i16 -> i16, I16, I8, U8);
+    impl_as!(as_i32 -> i32, I32, I16, I8, U16, U8);
+    impl_as!(as_i64 -> i64, I64, I32, I16, I8, U32, U16, U8);
+
+    impl_as!(as_u8 -> u8, U8);
+    impl_as!(as_u16 -> u16, U16, U8);
+    impl_as!(as_u32 -> u32, U32, U16, U8);
+    impl_as!(as_u64 -> u64, U64, U32, U16, U8);
+
+    impl_as!(as_f32 -> f32, F32, I16, U16, I8, U8);
+    impl_as!(as_f64 -> f64, F64, F32, I32, U32, I16, U16, I8, U8);
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ValueRef::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            ValueRef::String(v) => Some(v.as_bytes()),
+            ValueRef::Bytes(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ValueRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueRef::I8(v) => v.fmt(f),
+            ValueRef::U8(v) => v.fmt(f),
+            ValueRef::I16(v) => v.fmt(f),
+            ValueRef::U16(v) => v.fmt(f),
+            ValueRef::I32(v) => v.fmt(f),
+            ValueRef::U32(v) => v.fmt(f),
+            ValueRef::I64(v) => v.fmt(f),
+            ValueRef::U64(v) => v.fmt(f),
+            ValueRef::F32(v) => v.fmt(f),
+            ValueRef::F64(v) => v.fmt(f),
+            ValueRef::String(v) => v.fmt(f),
+            ValueRef::Bytes(v) => String::from_utf8_lossy(v).fmt(f),
+        }
+    }
+}
+
+fn take_slice<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], Error> {
+    let slice = pos
+        .checked_add(len)
+        .and_then(|end| data.get(*pos..end))
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    *pos += len;
+    Ok(slice)
+}
+
+fn take<const N: usize>(data: &[u8], pos: &mut usize) -> Result<[u8; N], Error> {
+    // PANIC slice is exactly N bytes long
+    Ok(take_slice(data, pos, N)?.try_into().unwrap())
+}
+
+/// Table that borrows its values from a byte buffer, e.g. a memory-mapped `.stc` file
+///
+/// Only the header is parsed upfront. Row offsets of a 100-row block are collected when the block
+/// is first accessed, values are decoded on access.
+pub struct BorrowedTable<'a> {
+    data: &'a [u8],
+    header: TableHeader,
+    // offset of every row in `data`, per block
+    blocks: Vec<OnceLock<Vec<usize>>>,
+}
+
+impl<'a> BorrowedTable<'a> {
+    /// Parse the header, `data` is expected to hold exactly one table
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        let header = TableHeader::read(&mut io::Cursor::new(data))?;
+
+        // rows start after the header, and within the buffer
+        for (entry, (_, offset)) in header.jump_table.iter().enumerate() {
+            let offset = u64::from(*offset);
+            if offset < header.size() || offset > data.len() as u64 {
+                return Err(Error::JumpTableOffsetOutOfBounds { entry, offset });
+            }
+        }
+
+        // empty tables are not checked, same as `Table::deserialize`
+        if header.rows != 0 {
+            // PANIC header is at least 6 bytes long
+            let size = data.len().checked_sub(4).unwrap();
+            if usize::from(header.last_block_size) != size % 65536 {
+                return Err(Error::LastBlockSizeMismatch);
+            }
+        }
+
+        let blocks = header.jump_table.iter().map(|_| OnceLock::new()).collect();
+
+        Ok(Self {
+            data,
+            header,
+            blocks,
+        })
+    }
+
+    pub fn id(&self) -> u16 {
+        self.header.id
+    }
+
+    pub fn header(&self) -> &TableHeader {
+        &self.header
+    }
+
+    pub fn column_types(&self) -> &[u8] {
+        &self.header.column_types
+    }
+
+    /// Amount of rows in the table
+    pub fn len(&self) -> usize {
+        self.header.rows.into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.rows == 0
+    }
+
+    /// Offsets of the rows of the `block`-th 100-row block, collected on first access
+    fn block(&self, block: usize) -> Result<&[usize], Error> {
+        let cell = &self.blocks[block];
+        if let Some(offsets) = cell.get() {
+            return Ok(offsets);
+        }
+
+        let (_, offset) = self.header.jump_table[block];
+        // PANIC offsets are checked to be within the buffer on parsing
+        let mut pos = usize::try_from(offset).unwrap();
+
+        let start = block * BLOCK_SIZE;
+        let end = self.len().min(start + BLOCK_SIZE);
+        let mut offsets = Vec::with_capacity(end - start);
+        for row_i in start..end {
+            offsets.push(pos);
+            for (column_i, t) in self.header.column_types.iter().enumerate() {
+                let offset = pos;
+                ValueRef::skip(*t, self.data, &mut pos).map_err(|err| Error::Decode {
+                    table_id: self.header.id,
+                    row: row_i,
+                    column: column_i,
+                    offset: offset as u64,
+                    source: Box::new(err),
+                })?;
+            }
+        }
+
+        Ok(cell.get_or_init(|| offsets))
+    }
+
+    fn row_at(&self, offset: usize) -> BorrowedRow<'a, '_> {
+        BorrowedRow {
+            data: self.data,
+            offset,
+            column_types: &self.header.column_types,
+        }
+    }
+
+    /// Row by its 0-based index
+    pub fn row(&self, row_i: usize) -> Result<Option<BorrowedRow<'a, '_>>, Error> {
+        if row_i >= self.len() {
+            return Ok(None);
+        }
+
+        let block = row_i / BLOCK_SIZE;
+        let offset = self.block(block)?[row_i % BLOCK_SIZE];
+        Ok(Some(self.row_at(offset)))
+    }
+
+    /// Find the row by its id
+    ///
+    /// See [`crate::TableReader::row`] for the order the blocks are searched in.
+    pub fn row_by_id(&self, row_id: i32) -> Result<Option<BorrowedRow<'a, '_>>, Error> {
+        for block in self.header.search_blocks(row_id) {
+            for offset in self.block(block)? {
+                let row = self.row_at(*offset);
+                if row.id() == Some(row_id) {
+                    return Ok(Some(row));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = Result<BorrowedRow<'a, '_>, Error>> {
+        // PANIC index is always in bounds
+        (0..self.len()).map(move |i| self.row(i).map(Option::unwrap))
+    }
+
+    pub fn get(&self, row_i: usize, column_i: usize) -> Result<ValueRef<'a>, Error> {
+        let row = self.row(row_i)?.ok_or(Error::RowNotFound)?;
+        row.get(column_i).ok_or(Error::ColumnNotFound)
+    }
+
+    pub fn value<T>(&self, row_i: usize, column_i: usize) -> Result<T, Error>
+    where
+        T: TryFrom<ValueRef<'a>>,
+    {
+        let value = self.get(row_i, column_i)?;
+
+        T::try_from(value).map_err(|_| Error::ValueConversionFailed {
+            table_id: self.id(),
+            row: row_i,
+            column: column_i,
+        })
+    }
+
+    /// Decode every row into an owned table
+    pub fn to_table(&self) -> Result<Table, Error> {
+        let mut table =
+            Table::with_schema(self.id(), Schema::new(self.header.column_types.clone()));
+        table.rows = self
+            .rows()
+            .map(|row| row.map(|row| row.to_values()))
+            .collect::<Result<_, _>>()?;
+        Ok(table)
+    }
+}
+
+/// Row of a [`BorrowedTable`], columns are decoded on access
+#[derive(Clone, Copy)]
+pub struct BorrowedRow<'a, 't> {
+    data: &'a [u8],
+    offset: usize,
+    column_types: &'t [u8],
+}
+
+impl<'a> BorrowedRow<'a, '_> {
+    pub fn len(&self) -> usize {
+        self.column_types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.column_types.is_empty()
+    }
+
+    pub fn id(&self) -> Option<i32> {
+        self.get(0).as_ref().and_then(ValueRef::as_i32)
+    }
+
+    pub fn get(&self, column_i: usize) -> Option<ValueRef<'a>> {
+        self.iter().nth(column_i)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ValueRef<'a>> + '_ {
+        let data = self.data;
+        let mut pos = self.offset;
+
+        // PANIC rows are only handed out once their block was walked, so every value is in bounds
+        self.column_types
+            .iter()
+            .map(move |t| ValueRef::read(*t, data, &mut pos).unwrap())
+    }
+
+    pub fn to_values(&self) -> Vec<Value> {
+        self.iter().map(|v| v.to_value()).collect()
+    }
+}
+
+macro_rules! impl_try_from {
+    ($type:ty, $fn:tt) => {
+        impl TryFrom<ValueRef<'_>> for $type {
+            type Error = InvalidType;
+
+            fn try_from(value: ValueRef<'_>) -> Result<Self, Self::Error> {
+                value.$fn().ok_or(InvalidType)
+            }
+        }
+    };
+}
+
+impl_try_from!(i8, as_i8);
+impl_try_from!(u8, as_u8);
+impl_try_from!(i16, as_i16);
+impl_try_from!(u16, as_u16);
+impl_try_from!(i32, as_i32);
+impl_try_from!(u32, as_u32);
+impl_try_from!(i64, as_i64);
+impl_try_from!(u64, as_u64);
+impl_try_from!(f32, as_f32);
+impl_try_from!(f64, as_f64);
+
+impl<'a> TryFrom<ValueRef<'a>> for &'a str {
+    type Error = InvalidType;
+
+    fn try_from(value: ValueRef<'a>) -> Result<Self, Self::Error> {
+        value.as_str().ok_or(InvalidType)
+    }
+}
+
+impl<'a> TryFrom<ValueRef<'a>> for &'a [u8] {
+    type Error = InvalidType;
+
+    fn try_from(value: ValueRef<'a>) -> Result<Self, Self::Error> {
+        value.as_bytes().ok_or(InvalidType)
+    }
+}
+
+impl From<ValueRef<'_>> for String {
+    fn from(v: ValueRef<'_>) -> Self {
+        v.to_string()
+    }
+}
+
+#[test]
+fn borrowed() {
+    let mut table = Table::new(5000);
+    for i in 0..150 {
+        table
+            .add_row(vec![
+                Value::I32(i),
+                Value::U8(i as u8),
+                Value::String(format!("row {}", i)),
+            ])
+            .unwrap();
+    }
+
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+    let buffer = buffer.into_inner();
+
+    let borrowed = BorrowedTable::parse(&buffer).unwrap();
+    assert_eq!(borrowed.id(), 5000);
+    assert_eq!(borrowed.len(), 150);
+    assert_eq!(borrowed.column_types(), &[5, 2, 11]);
+
+    assert!(matches!(borrowed.value::<i32>(120, 0), Ok(120)));
+    // only the block of the row was walked
+    assert!(borrowed.blocks[0].get().is_none());
+    assert!(borrowed.blocks[1].get().is_some());
+
+    assert!(matches!(borrowed.value::<i64>(120, 1), Ok(120)));
+    assert!(matches!(borrowed.value::<&str>(120, 2), Ok("row 120")));
+    assert!(matches!(
+        borrowed.value::<String>(120, 1).as_deref(),
+        Ok("120")
+    ));
+    assert!(matches!(
+        borrowed.value::<i32>(120, 2),
+        Err(Error::ValueConversionFailed {
+            table_id: 5000,
+            row: 120,
+            column: 2
+        })
+    ));
+    assert!(matches!(
+        borrowed.value::<i32>(150, 0),
+        Err(Error::RowNotFound)
+    ));
+    assert!(matches!(
+        borrowed.value::<i32>(0, 3),
+        Err(Error::ColumnNotFound)
+    ));
+
+    // strings point into the buffer
+    let string: &str = borrowed.value(1, 2).unwrap();
+    let range = buffer.as_ptr_range();
+    assert!(range.contains(&string.as_ptr()));
+
+    let row = borrowed.row_by_id(130).unwrap().unwrap();
+    assert_eq!(row.get(2), Some(ValueRef::String("row 130")));
+    assert!(borrowed.row_by_id(150).unwrap().is_none());
+
+    assert_eq!(borrowed.to_table().unwrap().rows, table.rows);
+
+    // truncated in the middle of the last string
+    let mut truncated = buffer[..buffer.len() - 1].to_vec();
+    assert!(matches!(
+        BorrowedTable::parse(&truncated),
+        Err(Error::LastBlockSizeMismatch)
+    ));
+    let lbs = (truncated.len() as u16 - 4).to_le_bytes();
+    truncated[2..4].copy_from_slice(&lbs);
+    let borrowed = BorrowedTable::parse(&truncated).unwrap();
+    assert!(matches!(borrowed.value::<i32>(20, 0), Ok(20)));
+    assert!(matches!(
+        borrowed.get(120, 0),
+        Err(Error::Decode {
+            table_id: 5000,
+            row: 149,
+            column: 2,
+            ..
+        })
+    ));
+    assert!(borrowed.to_table().is_err());
+
+    // first row would start inside the header
+    assert!(matches!(
+        BorrowedTable::parse(&[1, 0, 0, 0, 1, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+        Err(Error::JumpTableOffsetOutOfBounds {
+            entry: 0,
+            offset: 0
+        })
+    ));
+
+    // empty table without columns
+    let empty = BorrowedTable::parse(&[1, 0, 0, 0, 0, 0]).unwrap();
+    assert!(empty.is_empty());
+    assert!(empty.to_table().unwrap().rows.is_empty());
+}
+
+#[test]
+fn borrowed_lookup() {
+    // row out of order inside a block with ascending jump table ids
+    let mut table = Table::new(5000);
+    for i in 0..200 {
+        let id = if i == 50 { 1000 } else { i };
+        table.add_row(vec![Value::I32(id)]).unwrap();
+    }
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+
+    let borrowed = BorrowedTable::parse(buffer.get_ref()).unwrap();
+    assert_eq!(borrowed.row_by_id(1000).unwrap().unwrap().id(), Some(1000));
+    assert_eq!(borrowed.row_by_id(150).unwrap().unwrap().id(), Some(150));
+    assert!(borrowed.row_by_id(50).unwrap().is_none());
+}
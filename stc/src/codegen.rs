@@ -0,0 +1,400 @@
+//! Typed Rust API and TypeScript declarations generated from table definitions
+//!
+//! Meant to be called from `build.rs`:
+//!
+//! ```no_run
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("tables.rs");
+//! stc::codegen::write_rust("definitions.txt", out).unwrap();
+//! ```
+//!
+//! and then pulled in with `include!(concat!(env!("OUT_DIR"), "/tables.rs"));`.
+
+use std::{collections::HashSet, fmt::Write, fs, path::Path};
+
+use crate::{
+    definitions::{self, TableDefinition, TableDefinitions},
+    Error, Value,
+};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+    "while", "yield",
+];
+
+// keywords that can't be used as raw identifiers
+const RESERVED: &[&str] = &["crate", "self", "super", "Self", "abstract", "become"];
+
+/// Split the name into lowercase words, on non-alphanumeric characters and camelCase boundaries
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lowercase = false;
+
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lowercase = false;
+            continue;
+        }
+
+        if c.is_ascii_uppercase() && previous_lowercase {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c.to_ascii_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn identifier(mut name: String) -> String {
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+
+    if RESERVED.contains(&name.as_str()) {
+        name.push('_');
+    } else if KEYWORDS.contains(&name.as_str()) {
+        name.insert_str(0, "r#");
+    }
+
+    name
+}
+
+fn pascal_case(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn snake_case(words: &[String]) -> String {
+    words.join("_")
+}
+
+fn upper_snake_case(words: &[String]) -> String {
+    snake_case(words).to_ascii_uppercase()
+}
+
+/// Identifier from the words that isn't in `taken` yet, numbered from 2 on collisions
+fn unique_identifier(
+    taken: &mut HashSet<String>,
+    words: Vec<String>,
+    case: fn(&[String]) -> String,
+) -> String {
+    let mut name = case(&words);
+    let mut suffix = 2;
+    while !taken.insert(name.clone()) {
+        let mut numbered = words.clone();
+        numbered.push(suffix.to_string());
+        name = case(&numbered);
+        suffix += 1;
+    }
+
+    identifier(name)
+}
+
+/// Names of the tables, in the order of `ids`
+///
+/// Definitions can differ only in case or punctuation, e.g. `gun` and `Gun`, so the names are
+/// deduplicated across the tables. Tables without a usable name are named after the id.
+fn table_names(defs: &TableDefinitions, ids: &[u16], case: fn(&[String]) -> String) -> Vec<String> {
+    let mut taken = HashSet::new();
+
+    ids.iter()
+        .map(|id| {
+            let mut words = words(&defs[id].name);
+            if words.is_empty() {
+                words = vec!["table".to_owned(), id.to_string()];
+            }
+            unique_identifier(&mut taken, words, case)
+        })
+        .collect()
+}
+
+fn rust_type(type_name: &str) -> Result<&'static str, Error> {
+    let rust_type = match Value::type_from_name(type_name).ok_or(Error::InvalidColumnType)? {
+        1 => "i8",
+        2 => "u8",
+        3 => "i16",
+        4 => "u16",
+        5 => "i32",
+        6 => "u32",
+        7 => "i64",
+        8 => "u64",
+        9 => "f32",
+        10 => "f64",
+        _ => "String",
+    };
+
+    Ok(rust_type)
+}
+
+/// Field names of the struct, in the order of the columns
+fn field_names(def: &TableDefinition) -> Vec<String> {
+    let mut taken = HashSet::new();
+
+    def.columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let mut words = words(column);
+            if words.is_empty() {
+                words = vec!["col".to_owned(), i.to_string()];
+            }
+            unique_identifier(&mut taken, words, snake_case)
+        })
+        .collect()
+}
+
+fn generate_table(
+    code: &mut String,
+    id: u16,
+    def: &TableDefinition,
+    struct_name: &str,
+) -> Result<(), Error> {
+    let fields = field_names(def);
+    let types = def
+        .types
+        .iter()
+        .map(|t| rust_type(t))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // PANIC writing into a string never fails
+    writeln!(code, "/// `{}`, table {}", def.name, id).unwrap();
+    writeln!(code, "#[derive(Debug, Clone, PartialEq)]").unwrap();
+    writeln!(code, "pub struct {} {{", struct_name).unwrap();
+    for (field, t) in fields.iter().zip(types.iter()) {
+        writeln!(code, "    pub {}: {},", field, t).unwrap();
+    }
+    writeln!(code, "}}\n").unwrap();
+
+    writeln!(code, "impl {} {{", struct_name).unwrap();
+    writeln!(code, "    pub const TABLE_ID: u16 = {};\n", id).unwrap();
+
+    writeln!(
+        code,
+        "    pub fn from_table(table: &stc::Table) -> Result<Vec<Self>, stc::Error> {{"
+    )
+    .unwrap();
+    writeln!(code, "        (0..table.rows.len())").unwrap();
+    writeln!(code, "            .map(|row| {{").unwrap();
+    writeln!(code, "                Ok(Self {{").unwrap();
+    for (column, field) in fields.iter().enumerate() {
+        writeln!(
+            code,
+            "                    {}: table.value(row, {})?,",
+            field, column
+        )
+        .unwrap();
+    }
+    writeln!(code, "                }})").unwrap();
+    writeln!(code, "            }})").unwrap();
+    writeln!(code, "            .collect()").unwrap();
+    writeln!(code, "    }}\n").unwrap();
+
+    writeln!(
+        code,
+        "    pub fn from_named_table(table: &stc::NamedTable) -> Result<Vec<Self>, stc::Error> {{"
+    )
+    .unwrap();
+    writeln!(code, "        table").unwrap();
+    writeln!(code, "            .row_ids()").unwrap();
+    writeln!(code, "            .map(|&row_id| {{").unwrap();
+    writeln!(code, "                Ok(Self {{").unwrap();
+    for (column, field) in def.columns.iter().zip(fields.iter()) {
+        writeln!(
+            code,
+            "                    {}: table.value(row_id, {:?})?,",
+            field, column
+        )
+        .unwrap();
+    }
+    writeln!(code, "                }})").unwrap();
+    writeln!(code, "            }})").unwrap();
+    writeln!(code, "            .collect()").unwrap();
+    writeln!(code, "    }}").unwrap();
+    writeln!(code, "}}").unwrap();
+
+    Ok(())
+}
+
+/// Generate Rust source with a struct per table, its loaders, and a `table_ids` module with the ids
+pub fn generate_rust(defs: &TableDefinitions) -> Result<String, Error> {
+    let mut ids: Vec<_> = defs.keys().copied().collect();
+    ids.sort_unstable();
+
+    let structs = table_names(defs, &ids, pascal_case);
+    let consts = table_names(defs, &ids, upper_snake_case);
+
+    let mut code = String::from("// Generated from table definitions, do not edit\n\n");
+
+    code.push_str("pub mod table_ids {\n");
+    for (id, name) in ids.iter().zip(consts.iter()) {
+        // PANIC writing into a string never fails
+        writeln!(code, "    pub const {}: u16 = {};", name, id).unwrap();
+    }
+    code.push_str("}\n");
+
+    for (id, name) in ids.iter().zip(structs.iter()) {
+        code.push('\n');
+        generate_table(&mut code, *id, &defs[id], name)?;
+    }
+
+    Ok(code)
+}
+
+fn typescript_type(type_name: &str) -> Result<&'static str, Error> {
+    match Value::type_from_name(type_name).ok_or(Error::InvalidColumnType)? {
+        11 => Ok("string"),
+        // 64-bit integers are exported as JSON numbers too
+        _ => Ok("number"),
+    }
+}
+
+/// Property name, quoted unless it's a valid identifier
+fn typescript_property(name: &str) -> String {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if valid {
+        name.to_owned()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+/// Generate `.d.ts` declarations with an interface per table row and a `TableId` enum
+///
+/// Rows are keyed by column names, as in the JSON produced by [`crate::NamedTable::to_json`].
+pub fn generate_typescript(defs: &TableDefinitions) -> Result<String, Error> {
+    let mut ids: Vec<_> = defs.keys().copied().collect();
+    ids.sort_unstable();
+
+    let names = table_names(defs, &ids, pascal_case);
+
+    let mut code = String::from("// Generated from table definitions, do not edit\n\n");
+
+    code.push_str("export enum TableId {\n");
+    for (id, name) in ids.iter().zip(names.iter()) {
+        // PANIC writing into a string never fails
+        writeln!(code, "    {} = {},", name, id).unwrap();
+    }
+    code.push_str("}\n\n");
+
+    code.push_str("export interface NamedTable<Row> {\n");
+    code.push_str("    id: TableId;\n");
+    code.push_str("    name: string;\n");
+    code.push_str("    columns: string[];\n");
+    code.push_str("    types: string[];\n");
+    code.push_str("    rows: { [rowId: string]: Row };\n");
+    code.push_str("}\n");
+
+    for (id, name) in ids.iter().zip(names.iter()) {
+        let def = &defs[id];
+
+        writeln!(code, "\n/** `{}`, table {} */", def.name, id).unwrap();
+        writeln!(code, "export interface {} {{", name).unwrap();
+        for (column, t) in def.columns.iter().zip(def.types.iter()) {
+            writeln!(
+                code,
+                "    {}: {};",
+                typescript_property(column),
+                typescript_type(t)?
+            )
+            .unwrap();
+        }
+        code.push_str("}\n");
+    }
+
+    Ok(code)
+}
+
+/// Read definitions from `definitions`, and write the generated source into `out`
+///
+/// Also tells cargo to rerun the build script when the definitions change.
+pub fn write_rust<P, Q>(definitions: P, out: Q) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let contents = fs::read_to_string(definitions.as_ref())?;
+    let code = generate_rust(&definitions::parse(&contents)?)?;
+    fs::write(out, code)?;
+
+    println!("cargo:rerun-if-changed={}", definitions.as_ref().display());
+
+    Ok(())
+}
+
+#[test]
+fn rust() {
+    let defs = definitions::parse(
+        "5005;gun;id,name,type,rankDisplay,type;i32,string,u8,f32,i64\n\
+         5006;equip_info;id;i32",
+    )
+    .unwrap();
+
+    let code = generate_rust(&defs).unwrap();
+    assert!(
+        code.contains("    pub const GUN: u16 = 5005;\n    pub const EQUIP_INFO: u16 = 5006;\n")
+    );
+    assert!(code.contains(
+        "pub struct Gun {\n    pub id: i32,\n    pub name: String,\n    pub r#type: u8,\n    pub rank_display: f32,\n    pub type_2: i64,\n}"
+    ));
+    assert!(code.contains("pub struct EquipInfo {\n    pub id: i32,\n}"));
+    assert!(code.contains("    pub const TABLE_ID: u16 = 5006;"));
+    assert!(code.contains("                    rank_display: table.value(row, 3)?,"));
+    assert!(
+        code.contains("                    rank_display: table.value(row_id, \"rankDisplay\")?,")
+    );
+
+    let defs = definitions::parse("5005;gun;id;bool").unwrap();
+    assert!(matches!(
+        generate_rust(&defs),
+        Err(Error::InvalidColumnType)
+    ));
+}
+
+#[test]
+fn typescript() {
+    let defs = definitions::parse(
+        "5005;gun;id,name,rank-display,exp;i32,string,f32,u64\n\
+         5006;equip_info;id;i32",
+    )
+    .unwrap();
+
+    let code = generate_typescript(&defs).unwrap();
+    assert!(code.contains("export enum TableId {\n    Gun = 5005,\n    EquipInfo = 5006,\n}"));
+    assert!(code.contains(
+        "export interface Gun {\n    id: number;\n    name: string;\n    \"rank-display\": number;\n    exp: number;\n}"
+    ));
+    assert!(code.contains("export interface EquipInfo {\n    id: number;\n}"));
+
+    let defs = definitions::parse("5005;gun;id;i32\n5006;Gun;id;i32\n5007;;id;i32").unwrap();
+    let code = generate_typescript(&defs).unwrap();
+    assert!(code.contains("    Gun = 5005,\n    Gun2 = 5006,\n    Table5007 = 5007,\n"));
+    assert!(code.contains("export interface Gun2 {"));
+
+    let defs = definitions::parse("5005;gun;id;bool").unwrap();
+    assert!(matches!(
+        generate_typescript(&defs),
+        Err(Error::InvalidColumnType)
+    ));
+}
@@ -0,0 +1,233 @@
+use std::{
+    cell::OnceCell,
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    definitions::{TableDefinition, TableDefinitions},
+    header, Error, NamedTable, NamedTableOptions, Table,
+};
+
+struct Entry {
+    path: PathBuf,
+    table: OnceCell<Result<NamedTable, Error>>,
+}
+
+/// Tables of a `stc` directory with their definitions attached
+///
+/// Tables are deserialized on first access, errors are kept per file instead of failing the whole dataset.
+pub struct Dataset {
+    definitions: TableDefinitions,
+    options: NamedTableOptions,
+    entries: BTreeMap<u16, Entry>,
+    // mapping from table name to table id
+    names: HashMap<String, u16>,
+    // files whose table id could not be read
+    errors: Vec<(PathBuf, Error)>,
+}
+
+impl Dataset {
+    /// Every `.stc` file of the directory
+    pub fn open_dir<P>(path: P, definitions: TableDefinitions) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "stc") {
+                files.push(path);
+            }
+        }
+        // directory order is not stable
+        files.sort();
+
+        Ok(Self::from_files(files, definitions))
+    }
+
+    /// Files sharing a table id with a previous one are skipped and reported in [`Dataset::errors`]
+    pub fn from_files<I, P>(files: I, definitions: TableDefinitions) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        let mut dataset = Self {
+            definitions,
+            options: NamedTableOptions::default(),
+            entries: BTreeMap::new(),
+            names: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        for path in files.into_iter().map(Into::into) {
+            let id = match header::table_id(&path, || Ok(fs::File::open(&path)?)) {
+                Ok(id) => id,
+                Err(err) => {
+                    dataset.errors.push((path, err));
+                    continue;
+                }
+            };
+
+            // first file with the id is kept
+            if dataset.entries.contains_key(&id) {
+                dataset.errors.push((path, Error::DuplicateTableId(id)));
+                continue;
+            }
+
+            if let Some(def) = dataset.definitions.get(&id) {
+                dataset.names.insert(def.name.clone(), id);
+            }
+            dataset.entries.insert(
+                id,
+                Entry {
+                    path,
+                    table: OnceCell::new(),
+                },
+            );
+        }
+
+        dataset
+    }
+
+    /// Options used to attach definitions to tables
+    pub fn with_options(mut self, options: NamedTableOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Ids of all tables, in ascending order
+    pub fn ids(&self) -> impl Iterator<Item = u16> + '_ {
+        self.entries.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn load(&self, id: u16, entry: &Entry) -> Result<NamedTable, Error> {
+        let mut reader = BufReader::new(fs::File::open(&entry.path)?);
+        let table = Table::deserialize(&mut reader)?;
+
+        match self.definitions.get(&id) {
+            Some(def) => NamedTable::from_definition_with(table, def, &self.options),
+            // no column names to attach
+            None => {
+                let def = TableDefinition {
+                    name: id.to_string(),
+                    columns: Vec::new(),
+                    types: Vec::new(),
+                };
+                NamedTable::from_definition(table, &def)
+            }
+        }
+    }
+
+    fn get(&self, id: u16) -> Option<&Result<NamedTable, Error>> {
+        let entry = self.entries.get(&id)?;
+        Some(entry.table.get_or_init(|| self.load(id, entry)))
+    }
+
+    /// Table by its id, `None` if there's no such table or it failed to load
+    pub fn table_by_id(&self, id: u16) -> Option<&NamedTable> {
+        self.get(id)?.as_ref().ok()
+    }
+
+    /// Table by its name in the definitions, `None` if there's no such table or it failed to load
+    pub fn table(&self, name: &str) -> Option<&NamedTable> {
+        self.table_by_id(*self.names.get(name)?)
+    }
+
+    /// Error the table failed to load with
+    pub fn error(&self, id: u16) -> Option<&Error> {
+        self.get(id)?.as_ref().err()
+    }
+
+    /// Every table that loaded successfully, in the order of ids
+    pub fn tables(&self) -> impl Iterator<Item = &NamedTable> + '_ {
+        self.ids().filter_map(move |id| self.table_by_id(id))
+    }
+
+    /// Load every table, returning the files that failed
+    pub fn load_all(&self) -> Vec<(&Path, &Error)> {
+        for id in self.ids() {
+            self.get(id);
+        }
+
+        self.errors()
+    }
+
+    /// Files that failed so far, tables that were not accessed yet are not included
+    pub fn errors(&self) -> Vec<(&Path, &Error)> {
+        let loaded = self
+            .entries
+            .values()
+            .filter_map(|entry| match entry.table.get() {
+                Some(Err(err)) => Some((entry.path.as_path(), err)),
+                _ => None,
+            });
+
+        self.errors
+            .iter()
+            .map(|(path, err)| (path.as_path(), err))
+            .chain(loaded)
+            .collect()
+    }
+}
+
+#[test]
+fn dataset() {
+    use crate::Value;
+
+    let dir = std::env::temp_dir().join(format!("stc-dataset-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    for id in [5000, 5001] {
+        let mut table = Table::new(id);
+        table
+            .add_row(vec![Value::I32(1), Value::String(format!("table {}", id))])
+            .unwrap();
+        let mut file = fs::File::create(dir.join(format!("{}.stc", id))).unwrap();
+        table.serialize(&mut file).unwrap();
+    }
+    // truncated table
+    fs::write(dir.join("5002.stc"), [0x8a, 0x13, 0, 0, 1, 0]).unwrap();
+    // id can't be read
+    fs::write(dir.join("broken.stc"), [0]).unwrap();
+    // id is taken by 5001.stc
+    fs::copy(dir.join("5001.stc"), dir.join("copy.stc")).unwrap();
+    fs::write(dir.join("5003.txt"), "not a table").unwrap();
+
+    let definitions =
+        crate::definitions::parse("5000;gun;id,name;i32,string\n5002;equip;id;i32").unwrap();
+    let dataset = Dataset::open_dir(&dir, definitions).unwrap();
+
+    assert_eq!(dataset.ids().collect::<Vec<_>>(), [5000, 5001, 5002]);
+    assert_eq!(dataset.errors().len(), 2);
+    assert!(dataset
+        .errors()
+        .iter()
+        .any(|(path, err)| path.ends_with("copy.stc")
+            && matches!(err, Error::DuplicateTableId(5001))));
+
+    let gun = dataset.table("gun").unwrap();
+    assert_eq!(gun.value::<String>(1, "name").unwrap(), "table 5000");
+    // tables without definition are available by id
+    assert_eq!(dataset.table_by_id(5001).unwrap().name, "5001");
+    assert!(dataset.table("equip").is_none());
+    assert!(dataset.table("skin").is_none());
+
+    let errors = dataset.load_all();
+    assert_eq!(errors.len(), 3);
+    assert!(errors.iter().any(|(path, _)| path.ends_with("5002.stc")));
+    assert!(matches!(dataset.error(5002), Some(Error::Io(_))));
+    assert_eq!(dataset.tables().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
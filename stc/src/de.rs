@@ -0,0 +1,164 @@
+use std::fmt;
+
+use serde::de::{self, value::BorrowedStrDeserializer, DeserializeSeed, MapAccess, Visitor};
+
+use crate::{Error, Value};
+
+/// Error raised while deserializing a row, `column` is filled in once the failing column is known
+#[derive(Debug)]
+pub(crate) struct DeError {
+    column: Option<usize>,
+    message: String,
+}
+
+impl DeError {
+    /// Map the error to the table location it came from
+    pub(crate) fn into_error(self, table_id: u16, row: usize) -> Error {
+        match self.column {
+            Some(column) => Error::ValueConversionFailed {
+                table_id,
+                row,
+                column,
+            },
+            None => Error::ColumnNotFound,
+        }
+    }
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self {
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Deserializes a row as a map keyed by column names
+pub(crate) struct RowDeserializer<'a, 'de> {
+    columns: &'a [&'de str],
+    row: &'de [Value],
+}
+
+impl<'a, 'de> RowDeserializer<'a, 'de> {
+    pub(crate) fn new(columns: &'a [&'de str], row: &'de [Value]) -> Self {
+        Self { columns, row }
+    }
+}
+
+impl<'a, 'de> de::Deserializer<'de> for RowDeserializer<'a, 'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(RowAccess {
+            columns: self.columns,
+            row: self.row,
+            index: 0,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct RowAccess<'a, 'de> {
+    columns: &'a [&'de str],
+    row: &'de [Value],
+    index: usize,
+}
+
+impl<'a, 'de> MapAccess<'de> for RowAccess<'a, 'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        // columns without a name can't be mapped to a field
+        if self.index >= self.columns.len().min(self.row.len()) {
+            return Ok(None);
+        }
+
+        let name = self.columns[self.index];
+        seed.deserialize(BorrowedStrDeserializer::new(name))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let column = self.index;
+        self.index += 1;
+
+        seed.deserialize(ValueDeserializer(&self.row[column]))
+            .map_err(|mut err| {
+                err.column.get_or_insert(column);
+                err
+            })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.columns.len().min(self.row.len()) - self.index)
+    }
+}
+
+struct ValueDeserializer<'de>(&'de Value);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::I8(v) => visitor.visit_i8(*v),
+            Value::U8(v) => visitor.visit_u8(*v),
+            Value::I16(v) => visitor.visit_i16(*v),
+            Value::U16(v) => visitor.visit_u16(*v),
+            Value::I32(v) => visitor.visit_i32(*v),
+            Value::U32(v) => visitor.visit_u32(*v),
+            Value::I64(v) => visitor.visit_i64(*v),
+            Value::U64(v) => visitor.visit_u64(*v),
+            Value::F32(v) => visitor.visit_f32(*v),
+            Value::F64(v) => visitor.visit_f64(*v),
+            Value::String(v) => visitor.visit_borrowed_str(v),
+            Value::RawString(v) => match std::str::from_utf8(&v.bytes) {
+                Ok(v) => visitor.visit_borrowed_str(v),
+                Err(_) => visitor.visit_borrowed_bytes(&v.bytes),
+            },
+        }
+    }
+
+    /// Values are never missing, so `Option` fields are always `Some`
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -1,7 +1,8 @@
-use crate::Error;
-use std::collections::HashMap;
+use crate::{Error, Value};
+use std::{cmp::Ordering, collections::HashMap, fmt, fs, path::Path};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableDefinition {
     pub name: String,
     pub columns: Vec<String>,
@@ -10,43 +11,464 @@ pub struct TableDefinition {
 
 pub type TableDefinitions = HashMap<u16, TableDefinition>;
 
+/// Column of the table with a type other than the one in the definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// 0-based column index
+    pub column: usize,
+    pub name: String,
+    pub expected: String,
+    pub found: u8,
+}
+
+/// Differences between the definition and the table it's applied to
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DefinitionReport {
+    /// Columns of the definition past the last column of the table
+    pub missing_columns: Vec<String>,
+    /// 0-based indices of the table columns without a name in the definition
+    pub extra_columns: Vec<usize>,
+    pub type_mismatches: Vec<TypeMismatch>,
+}
+
+impl DefinitionReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing_columns.is_empty()
+            && self.extra_columns.is_empty()
+            && self.type_mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for DefinitionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems = Vec::new();
+        if !self.missing_columns.is_empty() {
+            problems.push(format!(
+                "missing columns {}",
+                self.missing_columns.join(", ")
+            ));
+        }
+        if !self.extra_columns.is_empty() {
+            let columns: Vec<String> = self.extra_columns.iter().map(|c| c.to_string()).collect();
+            problems.push(format!("unnamed columns {}", columns.join(", ")));
+        }
+        for mismatch in self.type_mismatches.iter() {
+            problems.push(format!(
+                "column {} expects {}, found {}",
+                mismatch.name,
+                mismatch.expected,
+                Value::type_name(mismatch.found).unwrap_or("unknown")
+            ));
+        }
+
+        f.write_str(&problems.join("; "))
+    }
+}
+
+impl TableDefinition {
+    /// Compare the definition with the column types of the table
+    pub fn check(&self, column_types: &[u8]) -> DefinitionReport {
+        let mut report = DefinitionReport::default();
+
+        if let Some(missing) = self.columns.get(column_types.len()..) {
+            report.missing_columns = missing.to_vec();
+        }
+        report.extra_columns = (self.columns.len()..column_types.len()).collect();
+
+        for (column, (found, (name, expected))) in column_types
+            .iter()
+            .zip(self.columns.iter().zip(self.types.iter()))
+            .enumerate()
+        {
+            if Value::type_from_name(expected) != Some(*found) {
+                report.type_mismatches.push(TypeMismatch {
+                    column,
+                    name: name.clone(),
+                    expected: expected.clone(),
+                    found: *found,
+                });
+            }
+        }
+
+        report
+    }
+}
+
+/// Parse definitions, skipping the `REGION;VERSION` header if present
 pub fn parse(contents: &str) -> Result<TableDefinitions, Error> {
-    let mut definitions = HashMap::new();
+    DefinitionsFile::parse(contents).map(|file| file.tables)
+}
+
+fn parse_line(line: &str) -> Result<(u16, TableDefinition), Error> {
+    let mut line = line.split(';');
+    let id = line
+        .next()
+        .unwrap() // PANIC split on string always returns at least one item
+        .parse()
+        .map_err(Error::InvalidTableId)?;
+    let name = line.next().ok_or(Error::NoTableName)?.to_owned();
+    let columns: Vec<String> = line
+        .next()
+        .ok_or(Error::NoTableColumnNames)?
+        .split(',')
+        .map(String::from)
+        .collect();
+    let types: Vec<String> = line
+        .next()
+        .ok_or(Error::NoTableColumnTypes)?
+        .split(',')
+        .map(String::from)
+        .collect();
+
+    if columns.len() != types.len() {
+        return Err(Error::InconsistentNamesAndTypesLength);
+    }
+
+    Ok((
+        id,
+        TableDefinition {
+            name,
+            columns,
+            types,
+        },
+    ))
+}
 
-    for line in contents.lines() {
-        let line = line.trim();
-        if line.starts_with("//") || line.is_empty() {
-            continue;
+/// Definitions file, optionally starting with a `REGION;VERSION` header
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DefinitionsFile {
+    pub region: Option<String>,
+    /// Data version the definitions were dumped from
+    pub version: Option<String>,
+    pub tables: TableDefinitions,
+}
+
+impl DefinitionsFile {
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        let mut file = Self::default();
+        let mut first = true;
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.starts_with("//") || line.is_empty() {
+                continue;
+            }
+
+            // header has two fields, and doesn't start with a table id
+            if std::mem::take(&mut first) {
+                let fields: Vec<&str> = line.split(';').collect();
+                if let [region, version] = fields[..] {
+                    if region.parse::<u16>().is_err() {
+                        file.region = Some(region.to_owned());
+                        file.version = Some(version.to_owned());
+                        continue;
+                    }
+                }
+            }
+
+            let (id, definition) = parse_line(line)?;
+            file.tables.insert(id, definition);
+        }
+
+        Ok(file)
+    }
+}
+
+impl fmt::Display for DefinitionsFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.region.is_some() || self.version.is_some() {
+            writeln!(
+                f,
+                "{};{}",
+                self.region.as_deref().unwrap_or("UNKNOWN_REGION"),
+                self.version.as_deref().unwrap_or("UNKNOWN_VERSION")
+            )?;
         }
 
-        let mut line = line.split(';');
-        let id = line
-            .next()
-            .unwrap() // PANIC split on string always returns at least one item
-            .parse()
-            .map_err(Error::InvalidTableId)?;
-        let name = line.next().ok_or(Error::NoTableName)?.to_owned();
-        let columns: Vec<String> = line
-            .next()
-            .ok_or(Error::NoTableColumnNames)?
-            .split(',')
-            .map(String::from)
+        f.write_str(&to_string(&self.tables))
+    }
+}
+
+/// Compare versions chunk by chunk, numeric chunks by their value, e.g. `2.10` > `2.9`
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn chunks(version: &str) -> impl Iterator<Item = &str> {
+        let mut rest = version;
+        std::iter::from_fn(move || {
+            let first = rest.chars().next()?;
+            let end = rest
+                .find(|c: char| c.is_ascii_digit() != first.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let (chunk, tail) = rest.split_at(end);
+            rest = tail;
+            Some(chunk)
+        })
+    }
+
+    let mut a = chunks(a);
+    let mut b = chunks(b);
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Definitions of several regions and data versions
+#[derive(Debug, Clone, Default)]
+pub struct DefinitionsRegistry {
+    files: Vec<DefinitionsFile>,
+}
+
+impl DefinitionsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse every file in the directory
+    pub fn load_dir<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut registry = Self::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let contents = fs::read_to_string(path)?;
+            registry.insert(DefinitionsFile::parse(&contents)?)?;
+        }
+
+        Ok(registry)
+    }
+
+    /// Add the file, replacing the one with the same region and version
+    ///
+    /// File must have a header.
+    pub fn insert(&mut self, file: DefinitionsFile) -> Result<Option<DefinitionsFile>, Error> {
+        if file.region.is_none() || file.version.is_none() {
+            return Err(Error::NoDefinitionsHeader);
+        }
+
+        let existing = self
+            .files
+            .iter()
+            .position(|f| f.region == file.region && f.version == file.version);
+
+        Ok(match existing {
+            Some(i) => Some(std::mem::replace(&mut self.files[i], file)),
+            None => {
+                self.files.push(file);
+                None
+            }
+        })
+    }
+
+    /// Regions in alphabetical order
+    pub fn regions(&self) -> Vec<&str> {
+        let mut regions: Vec<&str> = self
+            .files
+            .iter()
+            .filter_map(|f| f.region.as_deref())
             .collect();
-        let types: Vec<String> = line
-            .next()
-            .ok_or(Error::NoTableColumnTypes)?
-            .split(',')
-            .map(String::from)
+        regions.sort_unstable();
+        regions.dedup();
+        regions
+    }
+
+    /// Versions of the region, in ascending order
+    pub fn versions(&self, region: &str) -> Vec<&str> {
+        let mut versions: Vec<&str> = self
+            .files
+            .iter()
+            .filter(|f| f.region.as_deref() == Some(region))
+            .filter_map(|f| f.version.as_deref())
             .collect();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        versions
+    }
+
+    /// Definitions for the data version of the region
+    ///
+    /// Exact version is preferred, otherwise the latest version preceding the requested one is picked,
+    /// as definitions rarely change between data versions.
+    pub fn get(&self, region: &str, version: &str) -> Option<&DefinitionsFile> {
+        let files = self
+            .files
+            .iter()
+            .filter(|f| f.region.as_deref() == Some(region));
+
+        // PANIC files without version are rejected on insertion
+        files
+            .filter(|f| {
+                compare_versions(f.version.as_deref().unwrap(), version) != Ordering::Greater
+            })
+            .max_by(|a, b| {
+                compare_versions(a.version.as_deref().unwrap(), b.version.as_deref().unwrap())
+            })
+    }
+}
+
+/// Write definitions in the format read by [`parse`], ordered by table id
+pub fn to_string(definitions: &TableDefinitions) -> String {
+    let mut ids: Vec<_> = definitions.keys().collect();
+    ids.sort_unstable();
+
+    let mut contents = String::new();
+    for id in ids {
+        let def = &definitions[id];
+        contents.push_str(&format!(
+            "{};{};{};{}\n",
+            id,
+            def.name,
+            def.columns.join(","),
+            def.types.join(",")
+        ));
+    }
+
+    contents
+}
+
+fn field_type(field_type: &str) -> Option<&'static str> {
+    let t = match field_type {
+        "sbyte" | "SByte" => "i8",
+        "byte" | "Byte" => "u8",
+        "short" | "Int16" => "i16",
+        "ushort" | "UInt16" => "u16",
+        "int" | "Int32" => "i32",
+        "uint" | "UInt32" => "u32",
+        "long" | "Int64" => "i64",
+        "ulong" | "UInt64" => "u64",
+        "float" | "Single" => "f32",
+        "double" | "Double" => "f64",
+        "string" | "String" => "string",
+        _ => return None,
+    };
+
+    Some(t)
+}
+
+/// Name of the class declared on the line, e.g. `StcGun` in `public class StcGun // TypeDefIndex: 1`
+fn class_name(line: &str) -> Option<&str> {
+    let start = line.find("class ")? + "class ".len();
+    let name = &line[start..];
+    let end = name
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(name.len());
+
+    Some(&name[..end])
+}
+
+/// Build definitions from `dump.cs` produced by [Il2CppDumper](https://github.com/Perfare/Il2CppDumper)
+///
+/// Table names and ids are taken from the order of `stc*List` entries of the `CmdDef` enum, starting with 5000,
+/// columns are taken from the fields of the matching `Stc*` classes.
+/// If `shift` is set, ids starting with that entry are shifted by one.
+pub fn from_il2cpp_dump(dump: &str, shift: Option<u16>) -> Result<TableDefinitions, Error> {
+    let mut lines = dump.lines().map(str::trim);
+
+    // table names from CmdDef enum
+    lines
+        .by_ref()
+        .find(|line| line.contains("enum CmdDef"))
+        .ok_or(Error::NoCmdDef)?;
+    if lines.next() != Some("{") {
+        return Err(Error::NoCmdDef);
+    }
+
+    let mut tables = Vec::new();
+    let mut table_id = 0;
+    for line in lines.take_while(|line| !line.is_empty() && *line != "}") {
+        let name = line
+            .find("CmdDef stc")
+            .map(|start| &line[start + "CmdDef stc".len()..])
+            .and_then(|name| name.rfind("List").map(|end| &name[..end]))
+            .filter(|name| !name.is_empty());
+
+        if let Some(name) = name {
+            if Some(table_id) == shift {
+                table_id += 1;
+            }
+            tables.push((5000 + table_id, name));
+            table_id += 1;
+        }
+    }
 
-        if columns.len() != types.len() {
-            return Err(Error::InconsistentNamesAndTypesLength);
+    // line index of every class declaration
+    let lines: Vec<&str> = dump.lines().map(str::trim).collect();
+    let mut classes = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(name) = class_name(line) {
+            classes.entry(name).or_insert(i);
+        }
+    }
+
+    let mut definitions = HashMap::new();
+    for (id, name) in tables {
+        let class = classes
+            .get(format!("Stc{}", name).as_str())
+            .ok_or_else(|| Error::NoStcClass {
+                table: name.to_owned(),
+            })?;
+
+        let mut columns = Vec::new();
+        let mut types = Vec::new();
+
+        // class body starts with the fields section, absent if class has no fields
+        let mut body = lines[class + 1..].iter().skip_while(|line| **line == "{");
+        if body.next() == Some(&"// Fields") {
+            for line in
+                body.take_while(|line| !line.is_empty() && !line.starts_with("//") && **line != "}")
+            {
+                // skip lua hot patching related fields
+                if line.contains("DelegateBridge") {
+                    continue;
+                }
+
+                // e.g. `public int id; // 0x10`
+                let declaration = match line.find(';') {
+                    Some(end) => &line[..end],
+                    None => continue,
+                };
+                let mut words = declaration.split_whitespace().rev();
+                let (field, field_type_name) = match (words.next(), words.next()) {
+                    (Some(field), Some(field_type)) => (field, field_type),
+                    _ => continue,
+                };
+
+                let t =
+                    field_type(field_type_name).ok_or_else(|| Error::UnrecognizedFieldType {
+                        table: name.to_owned(),
+                        field: field.to_owned(),
+                        field_type: field_type_name.to_owned(),
+                    })?;
+                columns.push(field.to_owned());
+                types.push(t.to_owned());
+            }
+        }
+
+        // tables always have the id column, and the definition couldn't be read back without columns
+        if columns.is_empty() {
+            return Err(Error::NoStcFields {
+                table: name.to_owned(),
+            });
         }
 
         definitions.insert(
             id,
             TableDefinition {
-                name,
+                name: name.to_owned(),
                 columns,
                 types,
             },
@@ -89,3 +511,183 @@ fn test() {
 
     assert_eq!(parse(defs).unwrap(), parsed_defs);
 }
+
+#[test]
+fn il2cpp_dump() {
+    let dump = r#"
+// Namespace:
+public enum CmdDef // TypeDefIndex: 1
+{
+	// Fields
+	public int value__; // 0x0
+	public const CmdDef stcGunList = 0;
+	public const CmdDef stcGunSkinList = 1;
+	public const CmdDef stcMissionList = 2;
+
+	// Methods
+}
+
+// Namespace:
+public class StcGunSkin // TypeDefIndex: 3
+{
+	// Fields
+	public int id; // 0x10
+	public string name; // 0x18
+
+	// Methods
+	public void .ctor() { }
+}
+
+// Namespace:
+public class StcGun : IStcData // TypeDefIndex: 2
+{
+	// Fields
+	private static DelegateBridge __Hotfix0_get_id;
+	public int id; // 0x10
+	public Single ratio; // 0x14
+	public ushort rank; // 0x18
+}
+
+public class StcMission
+{
+	// Fields
+	public int id; // 0x10
+	public string title; // 0x18
+
+	// Methods
+	public void .ctor() { }
+}
+"#;
+
+    let definitions = from_il2cpp_dump(dump, None).unwrap();
+    let contents = to_string(&definitions);
+    assert_eq!(
+        contents,
+        "5000;Gun;id,ratio,rank;i32,f32,u16\n\
+         5001;GunSkin;id,name;i32,string\n\
+         5002;Mission;id,title;i32,string\n"
+    );
+
+    // output of dump2def is readable and usable for codegen
+    let parsed = parse(&contents).unwrap();
+    assert_eq!(parsed.len(), 3);
+    assert_eq!(parsed[&5002].columns, ["id", "title"]);
+    assert_eq!(parsed[&5002].types, ["i32", "string"]);
+    assert!(crate::codegen::generate_rust(&parsed).is_ok());
+    assert!(crate::codegen::generate_typescript(&parsed).is_ok());
+
+    let definitions = from_il2cpp_dump(dump, Some(1)).unwrap();
+    assert_eq!(definitions[&5000].name, "Gun");
+    assert_eq!(definitions[&5002].name, "GunSkin");
+    assert_eq!(definitions[&5003].name, "Mission");
+
+    let broken = dump.replace("class StcMission", "class StcMissionInfo");
+    assert!(matches!(
+        from_il2cpp_dump(&broken, None),
+        Err(Error::NoStcClass { table }) if table == "Mission"
+    ));
+
+    let broken = dump.replace(
+        "// Fields\n\tpublic int id; // 0x10\n\tpublic string title;",
+        "// Methods\n\tpublic void Load();",
+    );
+    assert!(matches!(
+        from_il2cpp_dump(&broken, None),
+        Err(Error::NoStcFields { table }) if table == "Mission"
+    ));
+
+    let broken = dump.replace("public string name;", "public List<int> name;");
+    assert!(matches!(
+        from_il2cpp_dump(&broken, None),
+        Err(Error::UnrecognizedFieldType { field_type, .. }) if field_type == "List<int>"
+    ));
+
+    assert!(matches!(from_il2cpp_dump("", None), Err(Error::NoCmdDef)));
+}
+
+#[test]
+fn header() {
+    let contents = "en;3.0400\n5000;table_1;col_1,col_2;i32,i32\n";
+    let file = DefinitionsFile::parse(contents).unwrap();
+    assert_eq!(file.region.as_deref(), Some("en"));
+    assert_eq!(file.version.as_deref(), Some("3.0400"));
+    assert_eq!(file.tables[&5000].name, "table_1");
+    assert_eq!(file.to_string(), contents);
+    assert_eq!(parse(contents).unwrap(), file.tables);
+
+    // header only on the first line
+    assert!(matches!(
+        parse("5000;table_1;col_1;i32\nen;3.0400"),
+        Err(Error::InvalidTableId(_))
+    ));
+    // malformed table line is not mistaken for header
+    assert!(matches!(
+        parse("5000;table_1"),
+        Err(Error::NoTableColumnNames)
+    ));
+    assert!(DefinitionsFile::parse("").unwrap().version.is_none());
+}
+
+#[test]
+fn registry() {
+    let file = |region: &str, version: &str| {
+        DefinitionsFile::parse(&format!("{};{}\n5000;{};id;i32", region, version, version)).unwrap()
+    };
+
+    let mut registry = DefinitionsRegistry::new();
+    for (region, version) in [("en", "2.9"), ("en", "2.10"), ("en", "3.0"), ("cn", "2.9")] {
+        assert!(registry.insert(file(region, version)).unwrap().is_none());
+    }
+    assert!(registry.insert(file("en", "3.0")).unwrap().is_some());
+    assert!(matches!(
+        registry.insert(DefinitionsFile::default()),
+        Err(Error::NoDefinitionsHeader)
+    ));
+
+    assert_eq!(registry.regions(), ["cn", "en"]);
+    assert_eq!(registry.versions("en"), ["2.9", "2.10", "3.0"]);
+
+    let version = |region: &str, version: &str| {
+        registry
+            .get(region, version)
+            .and_then(|f| f.version.clone())
+    };
+    assert_eq!(version("en", "2.10").as_deref(), Some("2.10"));
+    assert_eq!(version("en", "2.11").as_deref(), Some("2.10"));
+    assert_eq!(version("en", "9").as_deref(), Some("3.0"));
+    assert_eq!(version("cn", "3.0").as_deref(), Some("2.9"));
+    assert_eq!(version("en", "2.8"), None);
+    assert_eq!(version("kr", "3.0"), None);
+}
+
+#[test]
+fn check() {
+    let def = TableDefinition {
+        name: "table_1".to_owned(),
+        columns: vec!["id".to_owned(), "name".to_owned(), "rank".to_owned()],
+        types: vec!["i32".to_owned(), "string".to_owned(), "u8".to_owned()],
+    };
+
+    assert!(def.check(&[5, 11, 2]).is_empty());
+
+    let report = def.check(&[5, 5]);
+    assert_eq!(report.missing_columns, ["rank"]);
+    assert!(report.extra_columns.is_empty());
+    assert_eq!(
+        report.type_mismatches,
+        [TypeMismatch {
+            column: 1,
+            name: "name".to_owned(),
+            expected: "string".to_owned(),
+            found: 5
+        }]
+    );
+    assert_eq!(
+        report.to_string(),
+        "missing columns rank; column name expects string, found i32"
+    );
+
+    let report = def.check(&[5, 11, 2, 9, 9]);
+    assert!(report.missing_columns.is_empty());
+    assert_eq!(report.extra_columns, [3, 4]);
+}
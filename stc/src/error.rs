@@ -1,4 +1,6 @@
-use std::{io, num::ParseIntError};
+use std::{fmt, io, num::ParseIntError};
+
+use crate::{definitions::DefinitionReport, Value};
 
 #[derive(Debug)]
 pub enum Error {
@@ -7,6 +9,9 @@ pub enum Error {
     #[cfg(feature = "csv")]
     Csv(csv::Error),
 
+    #[cfg(feature = "zip")]
+    Zip(zip::result::ZipError),
+
     // # DEFINITIONS
     FirstColumnNotI32,
 
@@ -21,9 +26,77 @@ pub enum Error {
     /// Column names and types lengths do not match
     InconsistentNamesAndTypesLength,
 
+    /// Definitions file has no `REGION;VERSION` header
+    NoDefinitionsHeader,
+
+    /// Definition does not match the table it's applied to
+    DefinitionMismatch(DefinitionReport),
+
+    // # IL2CPP DUMP
+    /// `CmdDef` enum is missing from the dump
+    NoCmdDef,
+
+    /// `CmdDef` entry has no matching `Stc` class
+    NoStcClass {
+        table: String,
+    },
+
+    /// `Stc` class has no fields to take the columns from
+    NoStcFields {
+        table: String,
+    },
+
+    /// Field of the `Stc` class has a type that can't be mapped to a column type
+    UnrecognizedFieldType {
+        table: String,
+        field: String,
+        field_type: String,
+    },
+
+    // # JSON
+    /// JSON does not have the expected structure
+    InvalidJson,
+
     // # DESERIALIZATION
     LastBlockSizeMismatch,
 
+    UnknownColumnType(u8),
+
+    /// Value could not be decoded
+    Decode {
+        table_id: u16,
+        /// 0-based row index
+        row: usize,
+        /// 0-based colum index
+        column: usize,
+        /// Absolute offset of the value in the table
+        offset: u64,
+        source: Box<Error>,
+    },
+
+    /// Jump table entry does not match the id of the row it points at
+    JumpTableRowIdMismatch {
+        /// 0-based jump table entry index
+        entry: usize,
+        expected: i32,
+        found: i32,
+    },
+
+    /// Jump table entry does not point at the start of its row
+    JumpTableOffsetMismatch {
+        /// 0-based jump table entry index
+        entry: usize,
+        expected: u32,
+        found: u64,
+    },
+
+    /// Jump table entry points into the header or past the end of the table
+    JumpTableOffsetOutOfBounds {
+        /// 0-based jump table entry index
+        entry: usize,
+        offset: u64,
+    },
+
     // # ADDING ROWS, SERIALIZATION
     /// Rows reached max capacity
     TooManyRows,
@@ -37,15 +110,60 @@ pub enum Error {
     /// Inconsitent amount of colums in adding row
     InconsistentRowLength,
 
+    /// Value type does not match the column type in the table schema
+    ColumnTypeMismatch {
+        /// 0-based colum index
+        column: usize,
+        expected: u8,
+        found: u8,
+    },
+
+    /// Amount of written rows does not match the amount declared upfront
+    RowCountMismatch {
+        expected: usize,
+        written: usize,
+    },
+
     /// String exceeded the 16-bit size limit
     StringTooBig,
 
     /// Bookmark out of bounds due to 32-bit limit
     BookmarkOutOfBounds,
 
+    // # ARCHIVE
+    /// Table replaces no entry of the archive
+    TableNotInArchive(u16),
+
+    // # UPDATE
+    /// Only plain `http` URLs are supported
+    UnsupportedUrl(String),
+
+    InvalidHttpResponse,
+
+    /// Response has a non-2xx status
+    HttpStatus(u16),
+
+    /// Redirected more times than [`crate::update::MAX_REDIRECTS`]
+    TooManyRedirects,
+
+    /// Downloaded archive has no tables in it
+    NoTablesInArchive,
+
     // # ACCESS
     RowNotFound,
 
+    /// Several rows share the id
+    DuplicateRowId(i32),
+
+    /// Several tables share the id
+    DuplicateTableId(u16),
+
+    /// Several rows share the value of the column with a unique index
+    IndexKeyNotUnique {
+        /// 0-based colum index
+        column: usize,
+    },
+
     ColumnNotFound,
 
     ValueConversionFailed {
@@ -62,6 +180,151 @@ pub enum Error {
     MismatchedLength,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(_) => write!(f, "i/o error"),
+            #[cfg(feature = "csv")]
+            Error::Csv(_) => write!(f, "csv error"),
+            #[cfg(feature = "zip")]
+            Error::Zip(_) => write!(f, "zip error"),
+            Error::FirstColumnNotI32 => write!(f, "first column is not i32"),
+            Error::InvalidTableId(_) => write!(f, "invalid table id"),
+            Error::NoTableName => write!(f, "table name is missing"),
+            Error::NoTableColumnNames => write!(f, "table column names are missing"),
+            Error::NoTableColumnTypes => write!(f, "table column types are missing"),
+            Error::InconsistentNamesAndTypesLength => {
+                write!(f, "amount of column names and types does not match")
+            }
+            Error::NoDefinitionsHeader => write!(f, "definitions file has no region and version"),
+            Error::DefinitionMismatch(report) => {
+                write!(f, "definition does not match the table: {}", report)
+            }
+            Error::NoCmdDef => write!(f, "CmdDef enum not found"),
+            Error::NoStcClass { table } => write!(f, "class Stc{} not found", table),
+            Error::NoStcFields { table } => write!(f, "class Stc{} has no fields", table),
+            Error::UnrecognizedFieldType {
+                table,
+                field,
+                field_type,
+            } => write!(
+                f,
+                "field {} of class Stc{} has unrecognized type {}",
+                field, table, field_type
+            ),
+            Error::InvalidJson => write!(f, "unexpected json structure"),
+            Error::LastBlockSizeMismatch => write!(f, "last block size does not match"),
+            Error::UnknownColumnType(t) => write!(f, "unknown column type {}", t),
+            Error::Decode {
+                table_id,
+                row,
+                column,
+                offset,
+                ..
+            } => write!(
+                f,
+                "failed to decode table {} at row {}, column {}, offset {:#x}",
+                table_id, row, column, offset
+            ),
+            Error::JumpTableRowIdMismatch {
+                entry,
+                expected,
+                found,
+            } => write!(
+                f,
+                "jump table entry {} expects row id {}, found {}",
+                entry, expected, found
+            ),
+            Error::JumpTableOffsetMismatch {
+                entry,
+                expected,
+                found,
+            } => write!(
+                f,
+                "jump table entry {} expects offset {:#x}, row starts at {:#x}",
+                entry, expected, found
+            ),
+            Error::JumpTableOffsetOutOfBounds { entry, offset } => write!(
+                f,
+                "jump table entry {} points outside of the rows at {:#x}",
+                entry, offset
+            ),
+            Error::TooManyRows => write!(f, "too many rows"),
+            Error::TooManyColumns => write!(f, "row has more than 255 columns"),
+            Error::InvalidRowId => write!(f, "first column in the row is not i32"),
+            Error::InconsistentRowLength => {
+                write!(f, "amount of columns in the row does not match")
+            }
+            Error::ColumnTypeMismatch {
+                column,
+                expected,
+                found,
+            } => {
+                let name = |t: u8| {
+                    Value::type_name(t)
+                        .map(String::from)
+                        .unwrap_or_else(|| format!("unknown type {}", t))
+                };
+                write!(
+                    f,
+                    "column {} expects {}, found {}",
+                    column,
+                    name(*expected),
+                    name(*found)
+                )
+            }
+            Error::RowCountMismatch { expected, written } => {
+                write!(f, "expected {} rows, {} written", expected, written)
+            }
+            Error::StringTooBig => write!(f, "string exceeds 65535 bytes"),
+            Error::BookmarkOutOfBounds => write!(f, "jump table offset exceeds 32 bits"),
+            Error::TableNotInArchive(id) => write!(f, "archive has no entry for table {}", id),
+            Error::UnsupportedUrl(url) => write!(f, "unsupported url {}", url),
+            Error::InvalidHttpResponse => write!(f, "invalid http response"),
+            Error::HttpStatus(status) => write!(f, "http status {}", status),
+            Error::TooManyRedirects => write!(f, "too many redirects"),
+            Error::NoTablesInArchive => write!(f, "archive has no tables"),
+            Error::RowNotFound => write!(f, "row not found"),
+            Error::DuplicateRowId(id) => write!(f, "several rows have id {}", id),
+            Error::DuplicateTableId(id) => write!(f, "several tables have id {}", id),
+            Error::IndexKeyNotUnique { column } => {
+                write!(
+                    f,
+                    "several rows have the same value in unique column {}",
+                    column
+                )
+            }
+            Error::ColumnNotFound => write!(f, "column not found"),
+            Error::ValueConversionFailed {
+                table_id,
+                row,
+                column,
+            } => write!(
+                f,
+                "failed to convert value of table {} at row {}, column {}",
+                table_id, row, column
+            ),
+            Error::InvalidColumnType => write!(f, "invalid column type"),
+            Error::MismatchedLength => write!(f, "length does not match the requested length"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            #[cfg(feature = "csv")]
+            Error::Csv(err) => Some(err),
+            #[cfg(feature = "zip")]
+            Error::Zip(err) => Some(err),
+            Error::InvalidTableId(err) => Some(err),
+            Error::Decode { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
         Self::Io(err)
@@ -74,3 +337,10 @@ impl From<csv::Error> for Error {
         Self::Csv(err)
     }
 }
+
+#[cfg(feature = "zip")]
+impl From<zip::result::ZipError> for Error {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::Zip(err)
+    }
+}
@@ -0,0 +1,108 @@
+use std::{io::Read, path::Path};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::{reader::BLOCK_SIZE, Error};
+
+/// Everything that precedes the row data in a `.stc` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableHeader {
+    pub id: u16,
+    /// Length of the last 65536 byte block, not counting `id` and itself
+    pub last_block_size: u16,
+    pub rows: u16,
+    pub column_types: Vec<u8>,
+    /// `(row_id, offset)` of every 100th row, starting with the first one
+    pub jump_table: Vec<(i32, u32)>,
+}
+
+impl TableHeader {
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let id = reader.read_u16::<LittleEndian>()?;
+        let last_block_size = reader.read_u16::<LittleEndian>()?;
+        let rows = reader.read_u16::<LittleEndian>()?;
+
+        let mut header = Self {
+            id,
+            last_block_size,
+            rows,
+            column_types: Vec::new(),
+            jump_table: Vec::new(),
+        };
+
+        let columns: usize = if rows == 0 {
+            // empty tables may end right after the row count
+            let mut columns = [0];
+            if reader.read(&mut columns)? == 0 {
+                return Ok(header);
+            }
+            columns[0].into()
+        } else {
+            reader.read_u8()?.into()
+        };
+        header.column_types.reserve(columns);
+        for _ in 0..columns {
+            header.column_types.push(reader.read_u8()?);
+        }
+
+        let jump_table_size = usize::from(rows).div_ceil(BLOCK_SIZE);
+        header.jump_table.reserve(jump_table_size);
+        for _ in 0..jump_table_size {
+            let row_id = reader.read_i32::<LittleEndian>()?;
+            let offset = reader.read_u32::<LittleEndian>()?;
+            header.jump_table.push((row_id, offset));
+        }
+
+        Ok(header)
+    }
+
+    /// Blocks to search for the row, in the order they should be searched
+    ///
+    /// If the ids in the jump table are ascending, the block that would hold the row comes first.
+    /// Rows inside a block may still be out of order, so every other block follows it.
+    pub(crate) fn search_blocks(&self, row_id: i32) -> impl Iterator<Item = usize> {
+        let sorted = self.jump_table.windows(2).all(|w| w[0].0 <= w[1].0);
+        let likely = if sorted {
+            self.jump_table
+                .partition_point(|(id, _)| *id <= row_id)
+                .checked_sub(1)
+        } else {
+            None
+        };
+
+        likely
+            .into_iter()
+            .chain((0..self.jump_table.len()).filter(move |block| Some(*block) != likely))
+    }
+
+    /// Size of the header in bytes, row data starts right after it
+    pub fn size(&self) -> u64 {
+        if self.rows == 0 && self.column_types.is_empty() {
+            return 6;
+        }
+
+        // id (2), lbs (2), rows (2), columns (1), column types, jump table
+        7 + self.column_types.len() as u64 + 8 * self.jump_table.len() as u64
+    }
+}
+
+/// Table id from the file name, e.g. `5000.stc`, or from the header of the file `open` returns
+pub(crate) fn table_id<R, F>(path: &Path, open: F) -> Result<u16, Error>
+where
+    R: Read,
+    F: FnOnce() -> Result<R, Error>,
+{
+    if let Some(id) = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.parse().ok())
+    {
+        return Ok(id);
+    }
+
+    Ok(open()?.read_u16::<LittleEndian>()?)
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::{table::Row, Error, Value};
+
+/// Hashable form of a [`Value`], integers of any width compare equal if their values are equal
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum IndexKey {
+    Integer(i128),
+    Float(u64),
+    String(Vec<u8>),
+}
+
+impl From<&Value> for IndexKey {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::I8(v) => Self::Integer((*v).into()),
+            Value::U8(v) => Self::Integer((*v).into()),
+            Value::I16(v) => Self::Integer((*v).into()),
+            Value::U16(v) => Self::Integer((*v).into()),
+            Value::I32(v) => Self::Integer((*v).into()),
+            Value::U32(v) => Self::Integer((*v).into()),
+            Value::I64(v) => Self::Integer((*v).into()),
+            Value::U64(v) => Self::Integer((*v).into()),
+            Value::F32(v) => Self::float((*v).into()),
+            Value::F64(v) => Self::float(*v),
+            Value::String(v) => Self::String(v.as_bytes().to_vec()),
+            Value::RawString(v) => Self::String(v.bytes.clone()),
+        }
+    }
+}
+
+impl IndexKey {
+    fn float(v: f64) -> Self {
+        // -0.0 and 0.0 have different bits
+        let v = if v == 0.0 { 0.0 } else { v };
+        Self::Float(v.to_bits())
+    }
+}
+
+/// Mapping from the values of a column to the indices of the rows holding them
+#[derive(Debug, Clone)]
+pub(crate) struct SecondaryIndex {
+    column: usize,
+    unique: bool,
+    rows: HashMap<IndexKey, Vec<usize>>,
+}
+
+impl SecondaryIndex {
+    pub(crate) fn build(rows: &[Row], column: usize, unique: bool) -> Result<Self, Error> {
+        let mut index = Self {
+            column,
+            unique,
+            rows: HashMap::new(),
+        };
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let key = row.get(column).ok_or(Error::ColumnNotFound)?.into();
+            index.check(&key, row_index)?;
+            index.insert(key, row_index);
+        }
+
+        Ok(index)
+    }
+
+    pub(crate) fn column(&self) -> usize {
+        self.column
+    }
+
+    pub(crate) fn unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Check that the key can be set on the row without breaking uniqueness
+    pub(crate) fn check(&self, key: &IndexKey, row_index: usize) -> Result<(), Error> {
+        match self.rows.get(key) {
+            Some(rows) if self.unique && rows.iter().any(|i| *i != row_index) => {
+                Err(Error::IndexKeyNotUnique {
+                    column: self.column,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: IndexKey, row_index: usize) {
+        let rows = self.rows.entry(key).or_default();
+        // keep rows in the order of the table
+        let position = rows.partition_point(|i| *i < row_index);
+        rows.insert(position, row_index);
+    }
+
+    pub(crate) fn remove(&mut self, key: &IndexKey, row_index: usize) {
+        if let Some(rows) = self.rows.get_mut(key) {
+            rows.retain(|i| *i != row_index);
+            if rows.is_empty() {
+                self.rows.remove(key);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, key: &IndexKey) -> &[usize] {
+        self.rows.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+}
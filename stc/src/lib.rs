@@ -1,10 +1,35 @@
+#[cfg(feature = "zip")]
+mod archive;
+mod borrowed;
+pub mod codegen;
+mod dataset;
+#[cfg(feature = "serde")]
+mod de;
 pub mod definitions;
 mod error;
+mod header;
+mod index;
+pub mod matcher;
 mod named;
+mod reader;
+mod row;
+mod schema;
 mod table;
+#[cfg(feature = "update")]
+pub mod update;
 mod value;
+mod writer;
 
+#[cfg(feature = "zip")]
+pub use archive::Archive;
+pub use borrowed::{BorrowedRow, BorrowedTable, ValueRef};
+pub use dataset::Dataset;
 pub use error::Error;
-pub use named::NamedTable;
-pub use table::Table;
-pub use value::Value;
+pub use header::TableHeader;
+pub use named::{DuplicatePolicy, NamedTable, NamedTableOptions};
+pub use reader::{Rows, TableReader};
+pub use row::RowRef;
+pub use schema::Schema;
+pub use table::{DeserializeOptions, Row, Table};
+pub use value::{RawString, Value};
+pub use writer::{BufferedTableWriter, TableWriter};
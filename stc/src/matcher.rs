@@ -0,0 +1,269 @@
+//! Detects shifted table ids by matching column types of the tables against the definitions
+//!
+//! Ids move when the game inserts a new `CmdDef` entry, so definitions dumped from another version
+//! may label tables wrong.
+
+use crate::{
+    definitions::{TableDefinition, TableDefinitions},
+    Value,
+};
+
+/// Definition picked for a table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Match {
+    /// Definition with the same id matches
+    Exact { table_id: u16 },
+    /// Definition with another id matches, ids are likely shifted
+    Remapped { table_id: u16, definition_id: u16 },
+    /// Several definitions match and none of them could be picked
+    Ambiguous { table_id: u16, candidates: Vec<u16> },
+    /// No definition matches
+    Unmatched { table_id: u16 },
+}
+
+impl Match {
+    pub fn table_id(&self) -> u16 {
+        match self {
+            Match::Exact { table_id }
+            | Match::Remapped { table_id, .. }
+            | Match::Ambiguous { table_id, .. }
+            | Match::Unmatched { table_id } => *table_id,
+        }
+    }
+
+    /// Id of the matched definition
+    pub fn definition_id(&self) -> Option<u16> {
+        match self {
+            Match::Exact { table_id } => Some(*table_id),
+            Match::Remapped { definition_id, .. } => Some(*definition_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MatchReport {
+    /// Matches ordered by table id
+    pub matches: Vec<Match>,
+}
+
+impl MatchReport {
+    /// `(table_id, definition_id)` of tables matched with a definition of another id
+    pub fn remaps(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.matches.iter().filter_map(|m| match m {
+            Match::Remapped {
+                table_id,
+                definition_id,
+            } => Some((*table_id, *definition_id)),
+            _ => None,
+        })
+    }
+
+    pub fn ambiguous(&self) -> impl Iterator<Item = (u16, &[u16])> + '_ {
+        self.matches.iter().filter_map(|m| match m {
+            Match::Ambiguous {
+                table_id,
+                candidates,
+            } => Some((*table_id, candidates.as_slice())),
+            _ => None,
+        })
+    }
+
+    pub fn unmatched(&self) -> impl Iterator<Item = u16> + '_ {
+        self.matches.iter().filter_map(|m| match m {
+            Match::Unmatched { table_id } => Some(*table_id),
+            _ => None,
+        })
+    }
+
+    /// Definitions keyed by the actual table ids
+    ///
+    /// Only matched tables are included, ambiguous and unmatched ones are left out rather than labeled wrong.
+    pub fn corrected(&self, definitions: &TableDefinitions) -> TableDefinitions {
+        self.matches
+            .iter()
+            .filter_map(|m| {
+                let definition = definitions.get(&m.definition_id()?)?;
+                Some((m.table_id(), definition.clone()))
+            })
+            .collect()
+    }
+}
+
+fn signature(definition: &TableDefinition) -> Option<Vec<u8>> {
+    definition
+        .types
+        .iter()
+        .map(|t| Value::type_from_name(t))
+        .collect()
+}
+
+/// Match `(table_id, column_types)` of every table against the definitions
+///
+/// When several definitions have the same column types, the one at the same id offset
+/// as the closest tables with a single candidate is picked.
+pub fn match_tables<I, T>(tables: I, definitions: &TableDefinitions) -> MatchReport
+where
+    I: IntoIterator<Item = (u16, T)>,
+    T: AsRef<[u8]>,
+{
+    let signatures: Vec<(u16, Vec<u8>)> = definitions
+        .iter()
+        .filter_map(|(id, definition)| Some((*id, signature(definition)?)))
+        .collect();
+
+    let mut candidates: Vec<(u16, Vec<u16>)> = tables
+        .into_iter()
+        .map(|(table_id, column_types)| {
+            let column_types = column_types.as_ref();
+            let mut candidates: Vec<u16> = if column_types.is_empty() {
+                // nothing to compare against
+                Vec::new()
+            } else {
+                signatures
+                    .iter()
+                    .filter(|(_, signature)| signature.as_slice() == column_types)
+                    .map(|(id, _)| *id)
+                    .collect()
+            };
+            candidates.sort_unstable();
+            (table_id, candidates)
+        })
+        .collect();
+
+    candidates.sort_unstable_by_key(|(table_id, _)| *table_id);
+
+    // id offset of the tables with a single candidate
+    let offset = |(table_id, candidates): &(u16, Vec<u16>)| match candidates[..] {
+        [definition_id] => Some(i32::from(definition_id) - i32::from(*table_id)),
+        _ => None,
+    };
+    let mut preceding = Vec::with_capacity(candidates.len());
+    let mut last = None;
+    for table in candidates.iter() {
+        preceding.push(last);
+        last = offset(table).or(last);
+    }
+    let mut following = vec![None; candidates.len()];
+    let mut last = None;
+    for (i, table) in candidates.iter().enumerate().rev() {
+        following[i] = last;
+        last = offset(table).or(last);
+    }
+
+    let matches = candidates
+        .into_iter()
+        .zip(preceding.into_iter().zip(following))
+        .map(|((table_id, candidates), offsets)| {
+            let definition_id = match candidates[..] {
+                [] => return Match::Unmatched { table_id },
+                [definition_id] => definition_id,
+                _ => {
+                    // shift happens somewhere between the neighbours
+                    let mut shifted: Vec<u16> = [offsets.0, offsets.1]
+                        .iter()
+                        .flatten()
+                        .map(|offset| i32::from(table_id) + offset)
+                        .filter_map(|id| candidates.iter().copied().find(|c| i32::from(*c) == id))
+                        .collect();
+                    shifted.dedup();
+
+                    match shifted[..] {
+                        [definition_id] => definition_id,
+                        [] if candidates.contains(&table_id) => table_id,
+                        _ => {
+                            return Match::Ambiguous {
+                                table_id,
+                                candidates,
+                            }
+                        }
+                    }
+                }
+            };
+
+            if definition_id == table_id {
+                Match::Exact { table_id }
+            } else {
+                Match::Remapped {
+                    table_id,
+                    definition_id,
+                }
+            }
+        })
+        .collect();
+
+    MatchReport { matches }
+}
+
+#[test]
+fn matching() {
+    let definitions = crate::definitions::parse(
+        "5000;a;id;i32\n\
+         5001;b;id,name;i32,string\n\
+         5002;c;id,rank;i32,u8\n\
+         5003;d;id,rank;i32,u8\n\
+         5004;e;id,ratio;i32,f32\n\
+         5005;f;id,ratio,name;i32,f32,string\n\
+         5006;g;id;i64",
+    )
+    .unwrap();
+
+    // a new table was inserted at 5002, following ids are shifted by one,
+    // 5003 could be either `c` or `d`, 5004 is `d` as its neighbours are shifted
+    let tables: Vec<(u16, Vec<u8>)> = vec![
+        (5000, vec![5]),
+        (5001, vec![5, 11]),
+        (5002, vec![5, 5, 5]),
+        (5003, vec![5, 2]),
+        (5004, vec![5, 2]),
+        (5005, vec![5, 9]),
+        (5006, vec![5, 9, 11]),
+        (5007, vec![]),
+        (5008, vec![5, 2]),
+    ];
+
+    let report = match_tables(tables, &definitions);
+    assert_eq!(
+        report.matches,
+        [
+            Match::Exact { table_id: 5000 },
+            Match::Exact { table_id: 5001 },
+            Match::Unmatched { table_id: 5002 },
+            Match::Ambiguous {
+                table_id: 5003,
+                candidates: vec![5002, 5003]
+            },
+            Match::Remapped {
+                table_id: 5004,
+                definition_id: 5003
+            },
+            Match::Remapped {
+                table_id: 5005,
+                definition_id: 5004
+            },
+            Match::Remapped {
+                table_id: 5006,
+                definition_id: 5005
+            },
+            Match::Unmatched { table_id: 5007 },
+            Match::Ambiguous {
+                table_id: 5008,
+                candidates: vec![5002, 5003]
+            },
+        ]
+    );
+
+    assert_eq!(
+        report.remaps().collect::<Vec<_>>(),
+        [(5004, 5003), (5005, 5004), (5006, 5005)]
+    );
+    assert_eq!(report.unmatched().collect::<Vec<_>>(), [5002, 5007]);
+    assert_eq!(report.ambiguous().count(), 2);
+
+    let corrected = report.corrected(&definitions);
+    assert_eq!(corrected.len(), 5);
+    assert_eq!(corrected[&5004].name, "d");
+    assert_eq!(corrected[&5005].name, "e");
+    assert_eq!(corrected[&5006].name, "f");
+    assert!(!corrected.contains_key(&5002));
+}
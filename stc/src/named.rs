@@ -1,20 +1,77 @@
 use std::{collections::HashMap, convert::TryFrom, hash::Hash, io, str::FromStr};
 
 use indexmap::{map::Keys, IndexMap};
+use json::JsonValue;
 
-use crate::{definitions::TableDefinition, table::Table, Error, Value};
+use crate::{
+    definitions::{DefinitionReport, TableDefinition},
+    index::{IndexKey, SecondaryIndex},
+    table::{json_strings, Table},
+    Error, RowRef, Schema, Value,
+};
+
+/// What to do with rows sharing the same id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    Error,
+    /// Look up the first row with the id
+    KeepFirst,
+    /// Look up the last row with the id
+    #[default]
+    KeepLast,
+    /// Look up the first row with the id, every row is available through [`NamedTable::row_indices`]
+    All,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NamedTableOptions {
+    /// Fail if the definition does not match the column count or types of the table,
+    /// otherwise the differences are only kept in [`NamedTable::definition_report`]
+    pub strict: bool,
+    pub duplicates: DuplicatePolicy,
+}
 
 pub struct NamedTable {
     pub name: String,
     // mapping from id column to row index
     id_to_index: IndexMap<i32, usize>,
+    // mapping from ids shared by several rows to all of their row indices
+    duplicates: IndexMap<i32, Vec<usize>>,
+    duplicate_policy: DuplicatePolicy,
     // mapping from column name to column index
     column_to_index: HashMap<String, usize>,
-    pub table: Table,
+    // secondary indexes by column index
+    indexes: HashMap<usize, SecondaryIndex>,
+    report: DefinitionReport,
+    table: Table,
 }
 
 impl NamedTable {
     pub fn from_definition(table: Table, def: &TableDefinition) -> Result<Self, Error> {
+        Self::from_definition_with(table, def, &NamedTableOptions::default())
+    }
+
+    pub fn from_definition_with(
+        mut table: Table,
+        def: &TableDefinition,
+        options: &NamedTableOptions,
+    ) -> Result<Self, Error> {
+        // tables without rows and schema have nothing to check against
+        let report = match table.column_types() {
+            column_types if column_types.is_empty() => DefinitionReport::default(),
+            column_types => def.check(&column_types),
+        };
+        if options.strict && !report.is_empty() {
+            return Err(Error::DefinitionMismatch(report));
+        }
+
+        // otherwise the schema would be taken from the first inserted row
+        if table.column_types().is_empty() {
+            if let Ok(schema) = Schema::from_type_names(&def.types) {
+                table.schema = schema;
+            }
+        }
+
         let column_to_index: HashMap<String, usize> = def
             .columns
             .clone()
@@ -23,18 +80,281 @@ impl NamedTable {
             .map(|(i, n)| (n, i))
             .collect();
 
-        let mut id_to_index = IndexMap::new();
-        for (row_index, row) in table.rows.iter().enumerate() {
-            let row_id = row.get(0).and_then(Value::as_i32).ok_or(Error::ColumnNotFound)?;
-            id_to_index.insert(row_id, row_index);
-        }
-
-        Ok(Self {
+        let mut named = Self {
             name: def.name.clone(),
+            id_to_index: IndexMap::new(),
+            duplicates: IndexMap::new(),
+            duplicate_policy: options.duplicates,
             column_to_index,
-            id_to_index,
+            indexes: HashMap::new(),
+            report,
             table,
-        })
+        };
+        named.reindex()?;
+
+        Ok(named)
+    }
+
+    /// Rebuild the row id mapping and secondary indexes from the rows
+    fn reindex(&mut self) -> Result<(), Error> {
+        let mut indices: IndexMap<i32, Vec<usize>> = IndexMap::new();
+        for (row_index, row) in self.table.rows.iter().enumerate() {
+            let row_id = row.first().and_then(Value::as_i32).ok_or(Error::ColumnNotFound)?;
+            indices.entry(row_id).or_default().push(row_index);
+        }
+
+        self.id_to_index.clear();
+        self.duplicates.clear();
+        for (row_id, rows) in indices {
+            let row_index = match self.duplicate_policy {
+                _ if rows.len() == 1 => rows[0],
+                DuplicatePolicy::Error => return Err(Error::DuplicateRowId(row_id)),
+                DuplicatePolicy::KeepLast => rows[rows.len() - 1],
+                DuplicatePolicy::KeepFirst | DuplicatePolicy::All => rows[0],
+            };
+
+            self.id_to_index.insert(row_id, row_index);
+            if rows.len() > 1 {
+                self.duplicates.insert(row_id, rows);
+            }
+        }
+
+        for index in self.indexes.values_mut() {
+            *index = SecondaryIndex::build(&self.table.rows, index.column(), index.unique())?;
+        }
+
+        Ok(())
+    }
+
+    fn create_index_impl(&mut self, column_name: &str, unique: bool) -> Result<(), Error> {
+        let column_index = *self
+            .column_to_index
+            .get(column_name)
+            .ok_or(Error::ColumnNotFound)?;
+        let index = SecondaryIndex::build(&self.table.rows, column_index, unique)?;
+        self.indexes.insert(column_index, index);
+
+        Ok(())
+    }
+
+    /// Index the column, so it can be searched with [`NamedTable::find_by`] without a full scan
+    ///
+    /// Index is kept up to date when rows change.
+    pub fn create_index(&mut self, column_name: &str) -> Result<(), Error> {
+        self.create_index_impl(column_name, false)
+    }
+
+    /// Same as [`NamedTable::create_index`], but no two rows may have the same value in the column
+    pub fn create_unique_index(&mut self, column_name: &str) -> Result<(), Error> {
+        self.create_index_impl(column_name, true)
+    }
+
+    /// Returns `false` if the column had no index
+    pub fn drop_index(&mut self, column_name: &str) -> bool {
+        match self.column_to_index.get(column_name) {
+            Some(column_index) => self.indexes.remove(column_index).is_some(),
+            None => false,
+        }
+    }
+
+    /// First row with the value in the column
+    pub fn find_by(&self, column_name: &str, value: &Value) -> Result<Option<RowRef<'_>>, Error> {
+        Ok(self.find_all_by(column_name, value)?.into_iter().next())
+    }
+
+    /// Every row with the value in the column, in the order of the table
+    ///
+    /// Integers match regardless of their width. Columns without an index are scanned.
+    pub fn find_all_by(&self, column_name: &str, value: &Value) -> Result<Vec<RowRef<'_>>, Error> {
+        let column_index = *self
+            .column_to_index
+            .get(column_name)
+            .ok_or(Error::ColumnNotFound)?;
+        let key = IndexKey::from(value);
+
+        let rows = match self.indexes.get(&column_index) {
+            Some(index) => index.get(&key).to_vec(),
+            None => self
+                .table
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| row.get(column_index).map(IndexKey::from).as_ref() == Some(&key))
+                .map(|(row_index, _)| row_index)
+                .collect(),
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|row_index| RowRef::new(&self.table, &self.column_to_index, row_index))
+            .collect())
+    }
+
+    /// Ids shared by several rows, in the order of their first row
+    pub fn duplicate_ids(&self) -> Vec<i32> {
+        self.duplicates.keys().copied().collect()
+    }
+
+    /// Indices of the rows with the id
+    ///
+    /// Every row with the id is returned with [`DuplicatePolicy::All`], otherwise only the one kept by the policy.
+    pub fn row_indices(&self, row_id: i32) -> Vec<usize> {
+        match (self.duplicate_policy, self.duplicates.get(&row_id)) {
+            (DuplicatePolicy::All, Some(rows)) => rows.clone(),
+            _ => self.id_to_index.get(&row_id).copied().into_iter().collect(),
+        }
+    }
+
+    /// Differences between the definition and the table
+    pub fn definition_report(&self) -> &DefinitionReport {
+        &self.report
+    }
+
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    pub fn into_table(self) -> Table {
+        self.table
+    }
+
+    fn index(&self, row_id: i32, column_name: &str) -> Result<(usize, usize), Error> {
+        let row_index = self.id_to_index.get(&row_id).ok_or(Error::RowNotFound)?;
+        let column_index = self
+            .column_to_index
+            .get(column_name)
+            .ok_or(Error::ColumnNotFound)?;
+
+        Ok((*row_index, *column_index))
+    }
+
+    /// Replace the value of the column, value must have the type of the column
+    pub fn set_value(&mut self, row_id: i32, column_name: &str, value: Value) -> Result<(), Error> {
+        let (row_index, column_index) = self.index(row_id, column_name)?;
+
+        // definition may name columns the table doesn't have
+        let expected = *self
+            .table
+            .schema
+            .types
+            .get(column_index)
+            .ok_or(Error::ColumnNotFound)?;
+        let found = value.type_as_u8();
+        if found != expected {
+            return Err(Error::ColumnTypeMismatch {
+                column: column_index,
+                expected,
+                found,
+            });
+        }
+
+        if column_index == 0 {
+            // PANIC type is checked above
+            let new_id = value.as_i32().unwrap();
+            match self.id_to_index.get(&new_id) {
+                Some(index) if *index != row_index => return Err(Error::DuplicateRowId(new_id)),
+                _ => {}
+            }
+        }
+
+        let key = IndexKey::from(&value);
+        if let Some(index) = self.indexes.get(&column_index) {
+            index.check(&key, row_index)?;
+        }
+
+        let previous = std::mem::replace(&mut self.table.rows[row_index][column_index], value);
+        if let Some(index) = self.indexes.get_mut(&column_index) {
+            index.remove(&IndexKey::from(&previous), row_index);
+            index.insert(key, row_index);
+        }
+
+        // row id changed
+        if column_index == 0 {
+            if let Err(err) = self.reindex() {
+                self.table.rows[row_index][column_index] = previous;
+                self.reindex()?;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append the row, its id must not be taken
+    pub fn insert_row(&mut self, row: Vec<Value>) -> Result<(), Error> {
+        let row_id = row.first().and_then(Value::as_i32).ok_or(Error::InvalidRowId)?;
+        if self.id_to_index.contains_key(&row_id) {
+            return Err(Error::DuplicateRowId(row_id));
+        }
+
+        // schema of an empty table is taken from the row, restored if the row is rejected
+        let schema = self.table.schema.clone();
+        self.table.add_row(row)?;
+        let row_index = self.table.rows.len() - 1;
+        let row = &self.table.rows[row_index];
+
+        let keys = self
+            .indexes
+            .values()
+            .map(|index| {
+                let value = row.get(index.column()).ok_or(Error::InconsistentRowLength)?;
+                let key = IndexKey::from(value);
+                index.check(&key, row_index)?;
+                Ok(key)
+            })
+            .collect::<Result<Vec<_>, Error>>();
+        let keys = match keys {
+            Ok(keys) => keys,
+            Err(err) => {
+                self.table.rows.pop();
+                self.table.schema = schema;
+                return Err(err);
+            }
+        };
+        for (index, key) in self.indexes.values_mut().zip(keys) {
+            index.insert(key, row_index);
+        }
+
+        self.id_to_index.insert(row_id, row_index);
+
+        Ok(())
+    }
+
+    /// Replace the row with the same id, or append it if the id is not taken
+    ///
+    /// Returns the replaced row.
+    pub fn upsert_row(&mut self, row: Vec<Value>) -> Result<Option<Vec<Value>>, Error> {
+        let row_id = row.first().and_then(Value::as_i32).ok_or(Error::InvalidRowId)?;
+        match self.id_to_index.get(&row_id) {
+            Some(row_index) => {
+                let row_index = *row_index;
+                self.table.schema.check_row(&row)?;
+                for index in self.indexes.values() {
+                    index.check(&IndexKey::from(&row[index.column()]), row_index)?;
+                }
+
+                let previous = std::mem::replace(&mut self.table.rows[row_index], row);
+                let row = &self.table.rows[row_index];
+                for index in self.indexes.values_mut() {
+                    index.remove(&IndexKey::from(&previous[index.column()]), row_index);
+                    index.insert(IndexKey::from(&row[index.column()]), row_index);
+                }
+
+                Ok(Some(previous))
+            }
+            None => self.insert_row(row).map(|_| None),
+        }
+    }
+
+    /// Remove the row that is looked up by the id
+    pub fn remove_row(&mut self, row_id: i32) -> Result<Vec<Value>, Error> {
+        let row_index = *self.id_to_index.get(&row_id).ok_or(Error::RowNotFound)?;
+        let row = self.table.rows.remove(row_index);
+
+        // indices of the following rows are shifted
+        self.reindex()?;
+
+        Ok(row)
     }
 
     #[cfg(feature = "csv")]
@@ -53,18 +373,14 @@ impl NamedTable {
     where
         W: io::Write,
     {
-        if self.table.rows.is_empty() {
+        if self.table.rows.is_empty() && self.table.schema.is_empty() {
             return Ok(writer);
         }
 
         let mut writer = csv::Writer::from_writer(writer);
 
         if with_names {
-            let mut column_names: Vec<(String, usize)> = self.column_to_index.clone().into_iter().collect();
-            column_names.sort_by(|a, b| a.1.cmp(&b.1));
-            let column_names = column_names.into_iter().map(|(name, _index)| name);
-
-            writer.write_record(column_names)?;
+            writer.write_record(self.column_names())?;
         }
 
         writer.flush()?;
@@ -73,24 +389,186 @@ impl NamedTable {
         self.table.to_csv(writer, false, with_types)
     }
 
-    pub fn row_ids(&self) -> Keys<i32, usize> {
+    /// Convert the table into
+    /// `{ "id": .., "name": .., "columns": [..], "types": [..], "rows": { "<row id>": { "<column>": .., .. }, .. } }`
+    ///
+    /// Rows are keyed by id, so tables with duplicate ids can't be converted, see [`Table::to_json`].
+    pub fn to_json(&self) -> Result<JsonValue, Error> {
+        let columns = self.exported_column_names();
+        let types = self.table.column_types();
+
+        let mut rows = JsonValue::new_object();
+        for row in self.table.rows.iter() {
+            let mut object = JsonValue::new_object();
+            for (name, value) in columns.iter().zip(row.iter()) {
+                object[name.as_str()] = value.to_json();
+            }
+
+            // PANIC row ids are checked on creation
+            let row_id = row.first().and_then(Value::as_i32).unwrap();
+            let key = row_id.to_string();
+            if rows.has_key(&key) {
+                return Err(Error::DuplicateRowId(row_id));
+            }
+            rows[key.as_str()] = object;
+        }
+
+        let mut object = JsonValue::new_object();
+        object["id"] = self.table.id.into();
+        object["name"] = self.name.as_str().into();
+        object["types"] = types
+            .iter()
+            .map(|t| Value::type_name(*t).unwrap_or("unknown"))
+            .collect::<Vec<_>>()
+            .into();
+        object["columns"] = columns.into();
+        object["rows"] = rows;
+
+        Ok(object)
+    }
+
+    /// Read the table from JSON produced by [`NamedTable::to_json`]
+    pub fn from_json(value: &JsonValue) -> Result<Self, Error> {
+        let id = value["id"].as_u16().ok_or(Error::InvalidJson)?;
+        let name = value["name"].as_str().ok_or(Error::InvalidJson)?;
+        let columns = json_strings(&value["columns"])?;
+        let types = json_strings(&value["types"])?;
+
+        let schema = Schema::from_type_names(&types)?;
+        if columns.len() != schema.len() {
+            return Err(Error::InconsistentNamesAndTypesLength);
+        }
+
+        if !value["rows"].is_object() {
+            return Err(Error::InvalidJson);
+        }
+
+        let mut table = Table::with_schema(id, schema);
+        for (row_i, (_, row)) in value["rows"].entries().enumerate() {
+            if !row.is_object() {
+                return Err(Error::InvalidJson);
+            }
+
+            let row = columns
+                .iter()
+                .zip(table.schema.types.iter())
+                .enumerate()
+                .map(|(col_i, (col, t))| {
+                    Value::from_json(*t, &row[*col]).ok_or(Error::ValueConversionFailed {
+                        table_id: id,
+                        row: row_i,
+                        column: col_i,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            table.add_row(row)?;
+        }
+
+        let def = TableDefinition {
+            name: name.to_owned(),
+            columns: columns.into_iter().map(String::from).collect(),
+            types: types.into_iter().map(String::from).collect(),
+        };
+
+        Self::from_definition(table, &def)
+    }
+
+    /// Column names for JSON and serde, columns without a name in the definition are named `col-<index>`
+    fn exported_column_names(&self) -> Vec<String> {
+        let mut columns: Vec<String> = self.column_names().into_iter().map(String::from).collect();
+        for i in columns.len()..self.table.column_types().len() {
+            columns.push(format!("col-{}", i));
+        }
+
+        columns
+    }
+
+    /// Column names from the definition, in the order of columns
+    pub fn column_names(&self) -> Vec<&str> {
+        let mut column_names: Vec<(&str, usize)> = self
+            .column_to_index
+            .iter()
+            .map(|(name, index)| (name.as_str(), *index))
+            .collect();
+        column_names.sort_by_key(|a| a.1);
+        column_names.into_iter().map(|(name, _index)| name).collect()
+    }
+
+    #[cfg(feature = "serde")]
+    /// Deserialize the row into `T`, filling its fields by column name
+    pub fn get<'de, T>(&'de self, row_id: i32) -> Result<T, Error>
+    where
+        T: serde::Deserialize<'de>,
+    {
+        let row_index = *self.id_to_index.get(&row_id).ok_or(Error::RowNotFound)?;
+        let columns = self.column_names();
+        self.deserialize_row(&columns, row_index)
+    }
+
+    #[cfg(feature = "serde")]
+    /// Deserialize every row into `T`, in the order of the table
+    pub fn iter_as<'de, T>(&'de self) -> impl Iterator<Item = Result<T, Error>> + 'de
+    where
+        T: serde::Deserialize<'de>,
+    {
+        let columns = self.column_names();
+        (0..self.table.rows.len()).map(move |row_index| self.deserialize_row(&columns, row_index))
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize_row<'de, T>(&'de self, columns: &[&'de str], row_index: usize) -> Result<T, Error>
+    where
+        T: serde::Deserialize<'de>,
+    {
+        let row = &self.table.rows[row_index];
+        T::deserialize(crate::de::RowDeserializer::new(columns, row))
+            .map_err(|err| err.into_error(self.table.id, row_index))
+    }
+
+    pub fn row_ids(&self) -> Keys<'_, i32, usize> {
         self.id_to_index.keys()
     }
 
+    /// Row looked up by the id
+    pub fn row(&self, row_id: i32) -> Option<RowRef<'_>> {
+        let row_index = self.id_to_index.get(&row_id)?;
+        Some(RowRef::new(&self.table, &self.column_to_index, *row_index))
+    }
+
+    /// Every row, in the order of the table
+    pub fn rows(&self) -> impl Iterator<Item = RowRef<'_>> + '_ {
+        (0..self.table.rows.len())
+            .map(move |row_index| RowRef::new(&self.table, &self.column_to_index, row_index))
+    }
+
     pub fn value<'a, T>(&'a self, row_id: i32, column_name: &str) -> Result<T, Error>
     where
         T: TryFrom<&'a Value>,
     {
-        let row_index = self.id_to_index.get(&row_id).ok_or(Error::RowNotFound)?;
+        self.row(row_id).ok_or(Error::RowNotFound)?.get(column_name)
+    }
+
+    /// Value of the column in every row returned by [`NamedTable::row_indices`]
+    pub fn values<'a, T>(&'a self, row_id: i32, column_name: &str) -> Result<Vec<T>, Error>
+    where
+        T: TryFrom<&'a Value>,
+    {
+        let rows = self.row_indices(row_id);
+        if rows.is_empty() {
+            return Err(Error::RowNotFound);
+        }
         let column_index = self
             .column_to_index
             .get(column_name)
             .ok_or(Error::ColumnNotFound)?;
-        self.table.value(*row_index, *column_index)
+
+        rows.into_iter()
+            .map(|row_index| self.table.value(row_index, *column_index))
+            .collect()
     }
 
-    pub fn array<'a, T>(
-        &'a self,
+    pub fn array<T>(
+        &self,
         row_id: i32,
         column_name: &str,
         separator: &str,
@@ -99,17 +577,13 @@ impl NamedTable {
     where
         T: FromStr,
     {
-        let ret = self.vector(row_id, column_name, separator)?;
-
-        if ret.len() != length {
-            Err(Error::MismatchedLength)
-        } else {
-            Ok(ret)
-        }
+        self.row(row_id)
+            .ok_or(Error::RowNotFound)?
+            .array(column_name, separator, length)
     }
 
-    pub fn vector<'a, T>(
-        &'a self,
+    pub fn vector<T>(
+        &self,
         row_id: i32,
         column_name: &str,
         separator: &str,
@@ -117,12 +591,9 @@ impl NamedTable {
     where
         T: FromStr,
     {
-        let row_index = self.id_to_index.get(&row_id).ok_or(Error::RowNotFound)?;
-        let column_index = self
-            .column_to_index
-            .get(column_name)
-            .ok_or(Error::ColumnNotFound)?;
-        self.table.vector(*row_index, *column_index, separator)
+        self.row(row_id)
+            .ok_or(Error::RowNotFound)?
+            .vector(column_name, separator)
     }
 
     pub fn map<K, V>(
@@ -136,12 +607,562 @@ impl NamedTable {
         K: FromStr + Eq + Hash,
         V: FromStr,
     {
-        let row_index = self.id_to_index.get(&row_id).ok_or(Error::RowNotFound)?;
-        let column_index = self
-            .column_to_index
-            .get(column_name)
-            .ok_or(Error::ColumnNotFound)?;
-        self.table
-            .map(*row_index, *column_index, pair_separator, kv_separator)
+        self.row(row_id)
+            .ok_or(Error::RowNotFound)?
+            .map(column_name, pair_separator, kv_separator)
+    }
+}
+
+#[cfg(feature = "serde")]
+/// Serialized as `{ id, name, columns, types, rows }`, where every row is a map keyed by column names
+impl serde::Serialize for NamedTable {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct};
+
+        struct Row<'a>(&'a [String], &'a [Value]);
+
+        impl serde::Serialize for Row<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut map = serializer.serialize_map(Some(self.1.len()))?;
+                for (name, value) in self.0.iter().zip(self.1.iter()) {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+        }
+
+        struct Rows<'a>(&'a [String], &'a [Vec<Value>]);
+
+        impl serde::Serialize for Rows<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut seq = serializer.serialize_seq(Some(self.1.len()))?;
+                for row in self.1.iter() {
+                    seq.serialize_element(&Row(self.0, row))?;
+                }
+                seq.end()
+            }
+        }
+
+        let columns = self.exported_column_names();
+        let types: Vec<&str> = self
+            .table
+            .column_types()
+            .iter()
+            .map(|t| Value::type_name(*t).unwrap_or("unknown"))
+            .collect();
+
+        let mut state = serializer.serialize_struct("NamedTable", 5)?;
+        state.serialize_field("id", &self.table.id)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("columns", &columns)?;
+        state.serialize_field("types", &types)?;
+        state.serialize_field("rows", &Rows(&columns, &self.table.rows))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NamedTable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "NamedTable")]
+        struct Data {
+            id: u16,
+            name: String,
+            columns: Vec<String>,
+            types: Vec<String>,
+            rows: Vec<HashMap<String, Value>>,
+        }
+
+        let data = Data::deserialize(deserializer)?;
+
+        let schema = Schema::from_type_names(&data.types).map_err(D::Error::custom)?;
+        let mut table = Table::with_schema(data.id, schema);
+        for mut row in data.rows {
+            let row = data
+                .columns
+                .iter()
+                .map(|name| row.remove(name))
+                .collect::<Option<Vec<Value>>>()
+                .ok_or_else(|| D::Error::custom(Error::ColumnNotFound))?;
+            table.add_row(row).map_err(D::Error::custom)?;
+        }
+
+        let def = TableDefinition {
+            name: data.name,
+            columns: data.columns,
+            types: data.types,
+        };
+
+        Self::from_definition(table, &def).map_err(D::Error::custom)
     }
 }
+
+#[test]
+fn json() {
+    let mut table = Table::new(1);
+    table
+        .add_row(vec![
+            Value::I32(-1),
+            Value::String("0,1,2".into()),
+            Value::String("a:0,b:1,c:2".into()),
+        ])
+        .unwrap();
+    let def = TableDefinition {
+        name: "Test".into(),
+        columns: vec!["id".into(), "array".into(), "map".into()],
+        types: vec!["i32".into(), "string".into(), "string".into()],
+    };
+    let named = NamedTable::from_definition(table, &def).unwrap();
+
+    let json = named.to_json().unwrap();
+    assert_eq!(
+        json.dump(),
+        r#"{"id":1,"name":"Test","types":["i32","string","string"],"columns":["id","array","map"],"rows":{"-1":{"id":-1,"array":"0,1,2","map":"a:0,b:1,c:2"}}}"#
+    );
+    let parsed = NamedTable::from_json(&json).unwrap();
+    assert_eq!(parsed.name, "Test");
+    assert_eq!(parsed.table().rows, named.table().rows);
+    assert!(matches!(parsed.value::<i32>(-1, "id"), Ok(-1)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let def = TableDefinition {
+        name: "Test".into(),
+        columns: vec!["id".into(), "name".into()],
+        types: vec!["i32".into(), "string".into()],
+    };
+    let mut table = Table::new(5000);
+    table
+        .add_row(vec![Value::I32(1), Value::String("a".into())])
+        .unwrap();
+    let named = NamedTable::from_definition(table, &def).unwrap();
+
+    let json = serde_json::to_string(&named).unwrap();
+    assert_eq!(
+        json,
+        r#"{"id":5000,"name":"Test","columns":["id","name"],"types":["i32","string"],"rows":[{"id":{"I32":1},"name":{"String":"a"}}]}"#
+    );
+
+    let parsed: NamedTable = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.name, "Test");
+    assert_eq!(parsed.table().rows, named.table().rows);
+    assert!(matches!(parsed.value::<i32>(1, "id"), Ok(1)));
+
+    let json = serde_json::to_string(named.table()).unwrap();
+    let parsed: Table = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.schema, named.table().schema);
+    assert_eq!(parsed.rows, named.table().rows);
+
+    // value of the wrong type
+    let json = r#"{"id":1,"name":"Test","columns":["id"],"types":["i32"],"rows":[{"id":{"U8":1}}]}"#;
+    assert!(serde_json::from_str::<NamedTable>(json).is_err());
+    let json = r#"{"id":1,"schema":{"types":[5,5],"names":null},"rows":[[{"I32":1},{"String":"x"}]]}"#;
+    assert!(serde_json::from_str::<Table>(json).is_err());
+    let json = r#"{"id":1,"schema":{"types":[5,99],"names":null},"rows":[]}"#;
+    assert!(serde_json::from_str::<Table>(json).is_err());
+
+    // columns without a name in the definition
+    let mut table = named.into_table();
+    table.rows[0].push(Value::U8(2));
+    table.schema.types.push(2);
+    let named = NamedTable::from_definition(table, &def).unwrap();
+    let json = serde_json::to_string(&named).unwrap();
+    assert!(json.contains(r#""columns":["id","name","col-2"]"#));
+    let parsed: NamedTable = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.table().rows, named.table().rows);
+    assert_eq!(parsed.column_names(), ["id", "name", "col-2"]);
+}
+
+#[test]
+fn validation() {
+    let def = TableDefinition {
+        name: "gun".into(),
+        columns: vec!["id".into(), "name".into()],
+        types: vec!["i32".into(), "string".into()],
+    };
+    let mut table = Table::new(5000);
+    table
+        .add_row(vec![Value::I32(1), Value::I32(2), Value::U8(3)])
+        .unwrap();
+
+    let named = NamedTable::from_definition(table.clone(), &def).unwrap();
+    assert_eq!(named.definition_report().extra_columns, [2]);
+    assert_eq!(named.definition_report().type_mismatches.len(), 1);
+
+    let strict = NamedTableOptions {
+        strict: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        NamedTable::from_definition_with(table, &def, &strict),
+        Err(Error::DefinitionMismatch(report)) if report.extra_columns == [2]
+    ));
+
+    let mut table = Table::new(5000);
+    table
+        .add_row(vec![Value::I32(1), Value::String("M1911".into())])
+        .unwrap();
+    let named = NamedTable::from_definition_with(table, &def, &strict).unwrap();
+    assert!(named.definition_report().is_empty());
+
+    // nothing to check against
+    assert!(NamedTable::from_definition_with(Table::new(5000), &def, &strict).is_ok());
+}
+
+#[test]
+fn duplicates() {
+    let def = TableDefinition {
+        name: "gun".into(),
+        columns: vec!["id".into(), "name".into()],
+        types: vec!["i32".into(), "string".into()],
+    };
+    let mut table = Table::new(5000);
+    for (id, name) in [(1, "a"), (2, "b"), (1, "c"), (3, "d"), (1, "e"), (3, "f")] {
+        table
+            .add_row(vec![Value::I32(id), Value::String(name.into())])
+            .unwrap();
+    }
+    assert_eq!(table.duplicate_ids(), [1, 3]);
+    // ordered by the first row, not the repeated one
+    let mut reversed = Table::new(5000);
+    for id in [1, 2, 2, 1] {
+        reversed
+            .add_row(vec![Value::I32(id), Value::String("a".into())])
+            .unwrap();
+    }
+    assert_eq!(reversed.duplicate_ids(), [1, 2]);
+    assert_eq!(
+        NamedTable::from_definition(reversed.clone(), &def)
+            .unwrap()
+            .duplicate_ids(),
+        reversed.duplicate_ids()
+    );
+
+    let with = |duplicates| {
+        let options = NamedTableOptions {
+            duplicates,
+            ..Default::default()
+        };
+        NamedTable::from_definition_with(table.clone(), &def, &options)
+    };
+
+    assert!(matches!(
+        with(DuplicatePolicy::Error),
+        Err(Error::DuplicateRowId(1))
+    ));
+
+    let named = with(DuplicatePolicy::KeepFirst).unwrap();
+    assert_eq!(named.duplicate_ids(), [1, 3]);
+    assert_eq!(named.value::<String>(1, "name").unwrap(), "a");
+    assert_eq!(named.row_indices(1), [0]);
+    assert_eq!(named.row_ids().copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+    let named = with(DuplicatePolicy::KeepLast).unwrap();
+    assert_eq!(named.value::<String>(1, "name").unwrap(), "e");
+    assert_eq!(named.values::<String>(3, "name").unwrap(), ["f"]);
+
+    let named = with(DuplicatePolicy::All).unwrap();
+    assert_eq!(named.value::<String>(1, "name").unwrap(), "a");
+    assert_eq!(named.row_indices(1), [0, 2, 4]);
+    assert_eq!(named.row_indices(2), [1]);
+    assert!(named.row_indices(4).is_empty());
+    assert_eq!(named.values::<String>(1, "name").unwrap(), ["a", "c", "e"]);
+    assert!(matches!(named.values::<String>(4, "name"), Err(Error::RowNotFound)));
+
+    // rows are keyed by id in JSON
+    assert!(matches!(named.to_json(), Err(Error::DuplicateRowId(1))));
+}
+
+#[test]
+fn mutation() {
+    let def = TableDefinition {
+        name: "gun".into(),
+        columns: vec!["id".into(), "name".into()],
+        types: vec!["i32".into(), "string".into()],
+    };
+    let mut named = NamedTable::from_definition(Table::new(5000), &def).unwrap();
+    let row = |id: i32, name: &str| vec![Value::I32(id), Value::String(name.into())];
+
+    named.insert_row(row(1, "a")).unwrap();
+    named.insert_row(row(2, "b")).unwrap();
+    named.insert_row(row(3, "c")).unwrap();
+    assert!(matches!(named.insert_row(row(1, "d")), Err(Error::DuplicateRowId(1))));
+    assert!(matches!(
+        named.insert_row(vec![Value::I32(4), Value::I32(4)]),
+        Err(Error::ColumnTypeMismatch { column: 1, .. })
+    ));
+    assert_eq!(named.value::<String>(3, "name").unwrap(), "c");
+
+    named.set_value(2, "name", Value::String("e".into())).unwrap();
+    assert_eq!(named.value::<String>(2, "name").unwrap(), "e");
+    assert!(matches!(
+        named.set_value(2, "name", Value::U8(0)),
+        Err(Error::ColumnTypeMismatch { column: 1, .. })
+    ));
+    assert!(matches!(
+        named.set_value(2, "rank", Value::U8(0)),
+        Err(Error::ColumnNotFound)
+    ));
+
+    // changing the id
+    named.set_value(2, "id", Value::I32(5)).unwrap();
+    assert!(matches!(named.value::<String>(2, "name"), Err(Error::RowNotFound)));
+    assert_eq!(named.value::<String>(5, "name").unwrap(), "e");
+
+    assert_eq!(named.upsert_row(row(5, "f")).unwrap(), Some(row(5, "e")));
+    assert_eq!(named.upsert_row(row(6, "g")).unwrap(), None);
+    assert_eq!(named.value::<String>(6, "name").unwrap(), "g");
+
+    assert_eq!(named.remove_row(1).unwrap(), row(1, "a"));
+    assert!(matches!(named.remove_row(1), Err(Error::RowNotFound)));
+    assert_eq!(named.value::<String>(3, "name").unwrap(), "c");
+    assert_eq!(named.value::<String>(6, "name").unwrap(), "g");
+    assert_eq!(named.row_ids().copied().collect::<Vec<_>>(), [5, 3, 6]);
+
+    // id taken by another row
+    assert!(matches!(
+        named.set_value(3, "id", Value::I32(6)),
+        Err(Error::DuplicateRowId(6))
+    ));
+    assert_eq!(named.value::<String>(3, "name").unwrap(), "c");
+    assert_eq!(named.value::<String>(6, "name").unwrap(), "g");
+    named.set_value(3, "id", Value::I32(3)).unwrap();
+
+    // schema of an empty table comes from the definition, not from the first row
+    let mut empty = NamedTable::from_definition(Table::new(5000), &def).unwrap();
+    assert!(matches!(
+        empty.insert_row(vec![Value::I32(1), Value::U8(3)]),
+        Err(Error::ColumnTypeMismatch { column: 1, .. })
+    ));
+    assert!(empty.row_ids().next().is_none());
+    empty.insert_row(row(1, "a")).unwrap();
+
+    // types unknown to the definition, schema is taken from the row and restored on failure
+    let unknown = TableDefinition {
+        types: vec!["i32".into(), "bool".into()],
+        ..def.clone()
+    };
+    let mut empty = NamedTable::from_definition(Table::new(5000), &unknown).unwrap();
+    empty.create_index("name").unwrap();
+    assert!(matches!(
+        empty.insert_row(vec![Value::I32(1)]),
+        Err(Error::InconsistentRowLength)
+    ));
+    assert!(empty.table().schema.is_empty());
+    empty.insert_row(row(1, "a")).unwrap();
+
+    // column of the definition is missing from the table
+    let longer = TableDefinition {
+        name: "gun".into(),
+        columns: vec!["id".into(), "name".into(), "rank".into()],
+        types: vec!["i32".into(), "string".into(), "u8".into()],
+    };
+    let mut longer = NamedTable::from_definition(named.table().clone(), &longer).unwrap();
+    assert!(!longer.definition_report().missing_columns.is_empty());
+    assert!(matches!(
+        longer.set_value(3, "rank", Value::U8(1)),
+        Err(Error::ColumnNotFound)
+    ));
+
+    // id taken, with duplicates rejected
+    let options = NamedTableOptions {
+        duplicates: DuplicatePolicy::Error,
+        ..Default::default()
+    };
+    let mut named = NamedTable::from_definition_with(named.into_table(), &def, &options).unwrap();
+    assert!(matches!(
+        named.set_value(3, "id", Value::I32(5)),
+        Err(Error::DuplicateRowId(5))
+    ));
+    assert_eq!(named.value::<String>(3, "name").unwrap(), "c");
+    assert_eq!(named.value::<String>(5, "name").unwrap(), "f");
+}
+
+#[test]
+fn row_view() {
+    let def = TableDefinition {
+        name: "gun".into(),
+        columns: vec!["id".into(), "name".into(), "drops".into(), "rates".into()],
+        types: vec!["i32".into(), "string".into(), "string".into(), "string".into()],
+    };
+    let mut table = Table::new(5000);
+    for (id, name) in [(1, "M1911"), (2, "M9")] {
+        table
+            .add_row(vec![
+                Value::I32(id),
+                Value::String(name.into()),
+                Value::String("1,2,3".into()),
+                Value::String("1:10,2:20".into()),
+            ])
+            .unwrap();
+    }
+    let named = NamedTable::from_definition(table, &def).unwrap();
+
+    let row = named.row(2).unwrap();
+    assert_eq!(row.id(), 2);
+    assert_eq!(row.index(), 1);
+    assert_eq!(row.get::<String>("name").unwrap(), "M9");
+    assert_eq!(row.value("name"), Some(&Value::String("M9".into())));
+    assert_eq!(row.value("rank"), None);
+    assert_eq!(row.values().len(), 4);
+    assert_eq!(row.vector::<u8>("drops", ",").unwrap(), [1, 2, 3]);
+    assert!(matches!(
+        row.array::<u8>("drops", ",", 2),
+        Err(Error::MismatchedLength)
+    ));
+    assert_eq!(row.map::<u8, u8>("rates", ",", ":").unwrap()[&2], 20);
+    assert!(matches!(row.get::<i32>("rank"), Err(Error::ColumnNotFound)));
+    assert!(named.row(3).is_none());
+
+    let names: Vec<String> = named.rows().map(|row| row.get("name").unwrap()).collect();
+    assert_eq!(names, ["M1911", "M9"]);
+}
+
+#[test]
+fn secondary_indexes() {
+    let def = TableDefinition {
+        name: "skin".into(),
+        columns: vec!["id".into(), "gun_id".into(), "code".into()],
+        types: vec!["i32".into(), "u16".into(), "string".into()],
+    };
+    let mut table = Table::new(5000);
+    for (id, gun_id, code) in [(1, 10, "a"), (2, 20, "b"), (3, 10, "c")] {
+        table
+            .add_row(vec![
+                Value::I32(id),
+                Value::U16(gun_id),
+                Value::String(code.into()),
+            ])
+            .unwrap();
+    }
+    let mut named = NamedTable::from_definition(table, &def).unwrap();
+    let ids = |rows: Vec<RowRef>| rows.iter().map(RowRef::id).collect::<Vec<_>>();
+
+    // scan without an index
+    assert_eq!(ids(named.find_all_by("gun_id", &Value::U16(10)).unwrap()), [1, 3]);
+
+    named.create_index("gun_id").unwrap();
+    named.create_unique_index("code").unwrap();
+    assert!(matches!(
+        named.create_unique_index("gun_id"),
+        Err(Error::IndexKeyNotUnique { column: 1 })
+    ));
+    assert!(matches!(named.create_index("rank"), Err(Error::ColumnNotFound)));
+
+    // integers of other widths match
+    assert_eq!(ids(named.find_all_by("gun_id", &Value::I32(10)).unwrap()), [1, 3]);
+    let code = Value::String("b".into());
+    assert_eq!(named.find_by("code", &code).unwrap().unwrap().id(), 2);
+    assert!(named.find_by("code", &Value::String("d".into())).unwrap().is_none());
+
+    // indexes follow the changes
+    named.set_value(3, "gun_id", Value::U16(20)).unwrap();
+    assert_eq!(ids(named.find_all_by("gun_id", &Value::U16(10)).unwrap()), [1]);
+    assert_eq!(ids(named.find_all_by("gun_id", &Value::U16(20)).unwrap()), [2, 3]);
+    assert!(matches!(
+        named.set_value(3, "code", code.clone()),
+        Err(Error::IndexKeyNotUnique { column: 2 })
+    ));
+
+    let row = |id: i32, gun_id: u16, code: &str| {
+        vec![Value::I32(id), Value::U16(gun_id), Value::String(code.into())]
+    };
+    assert!(matches!(
+        named.insert_row(row(4, 10, "a")),
+        Err(Error::IndexKeyNotUnique { column: 2 })
+    ));
+    assert_eq!(named.table().rows.len(), 3);
+    named.insert_row(row(4, 10, "d")).unwrap();
+    named.upsert_row(row(1, 30, "e")).unwrap();
+    assert!(named.find_by("code", &Value::String("a".into())).unwrap().is_none());
+    assert_eq!(ids(named.find_all_by("gun_id", &Value::U16(10)).unwrap()), [4]);
+
+    named.remove_row(2).unwrap();
+    assert_eq!(ids(named.find_all_by("gun_id", &Value::U16(20)).unwrap()), [3]);
+    assert_eq!(named.find_by("code", &Value::String("d".into())).unwrap().unwrap().index(), 2);
+
+    assert!(named.drop_index("code"));
+    assert!(!named.drop_index("code"));
+    assert_eq!(named.find_by("code", &Value::String("e".into())).unwrap().unwrap().id(), 1);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_rows() {
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Gun<'a> {
+        id: i32,
+        name: &'a str,
+        rank: u16,
+        ratio: Option<f64>,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Missing {
+        #[allow(dead_code)]
+        missing: i32,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Wrong {
+        #[allow(dead_code)]
+        name: i32,
+    }
+
+    let def = TableDefinition {
+        name: "gun".into(),
+        columns: vec!["id".into(), "name".into(), "rank".into(), "ratio".into()],
+        types: vec!["i32".into(), "string".into(), "u8".into(), "f32".into()],
+    };
+    let mut table = Table::new(5000);
+    for (id, name) in [(1, "M1911"), (2, "M9")] {
+        table
+            .add_row(vec![
+                Value::I32(id),
+                Value::String(name.into()),
+                Value::U8(id as u8 + 1),
+                Value::F32(0.5),
+            ])
+            .unwrap();
+    }
+    let named = NamedTable::from_definition(table, &def).unwrap();
+
+    let gun: Gun = named.get(2).unwrap();
+    assert_eq!(
+        gun,
+        Gun {
+            id: 2,
+            name: "M9",
+            rank: 3,
+            ratio: Some(0.5)
+        }
+    );
+
+    let guns: Vec<Gun> = named.iter_as().collect::<Result<_, _>>().unwrap();
+    assert_eq!(guns.len(), 2);
+    assert_eq!(guns[0].name, "M1911");
+
+    assert!(matches!(named.get::<Gun>(3), Err(Error::RowNotFound)));
+    assert!(matches!(named.get::<Missing>(1), Err(Error::ColumnNotFound)));
+    assert!(matches!(
+        named.get::<Wrong>(2),
+        Err(Error::ValueConversionFailed { table_id: 5000, row: 1, column: 1 })
+    ));
+}
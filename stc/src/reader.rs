@@ -0,0 +1,321 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{table::Row, Error, TableHeader, Value};
+
+/// Amount of rows between two jump table entries
+pub(crate) const BLOCK_SIZE: usize = 100;
+
+/// Lazy table reader, decodes rows only when they are requested
+///
+/// Header and the jump table are parsed upfront, rows are looked up through the jump table,
+/// so only the 100-row block holding the requested row is decoded.
+pub struct TableReader<R> {
+    reader: R,
+    header: TableHeader,
+    // current position in the reader
+    offset: u64,
+}
+
+impl<'a> TableReader<io::Cursor<&'a [u8]>> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::new(io::Cursor::new(bytes))
+    }
+}
+
+impl<R> TableReader<R>
+where
+    R: Read + Seek,
+{
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let header = TableHeader::read(&mut reader)?;
+        let offset = reader.stream_position()?;
+
+        Ok(Self {
+            reader,
+            header,
+            offset,
+        })
+    }
+
+    pub fn header(&self) -> &TableHeader {
+        &self.header
+    }
+
+    pub fn id(&self) -> u16 {
+        self.header.id
+    }
+
+    /// Amount of rows in the table
+    pub fn len(&self) -> usize {
+        self.header.rows.into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.rows == 0
+    }
+
+    pub fn column_types(&self) -> &[u8] {
+        &self.header.column_types
+    }
+
+    /// `(row_id, offset)` pairs of every 100th row
+    pub fn jump_table(&self) -> &[(i32, u32)] {
+        &self.header.jump_table
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decode all rows of the `block`-th 100-row block
+    pub fn block(&mut self, block: usize) -> Result<Vec<Row>, Error> {
+        let (_, offset) = *self
+            .header
+            .jump_table
+            .get(block)
+            .ok_or(Error::RowNotFound)?;
+        self.seek(offset)?;
+
+        let start = block * BLOCK_SIZE;
+        let len = BLOCK_SIZE.min(self.len() - start);
+        let mut rows = Vec::with_capacity(len);
+        for row_i in start..start + len {
+            rows.push(self.read_row(row_i)?);
+        }
+
+        Ok(rows)
+    }
+
+    /// Decode the row by its 0-based index
+    pub fn row_at(&mut self, index: usize) -> Result<Option<Row>, Error> {
+        if index >= self.len() {
+            return Ok(None);
+        }
+
+        let (_, offset) = self.header.jump_table[index / BLOCK_SIZE];
+        self.seek(offset)?;
+
+        // step over the preceding rows of the block
+        let start = index - index % BLOCK_SIZE;
+        for row_i in start..index {
+            self.read_row(row_i)?;
+        }
+
+        self.read_row(index).map(Some)
+    }
+
+    /// Decode the row by its id
+    ///
+    /// If the ids in the jump table are ascending, the block that would hold the row is decoded first,
+    /// the rest are only decoded if it's not there.
+    pub fn row(&mut self, row_id: i32) -> Result<Option<Row>, Error> {
+        let blocks: Vec<usize> = self.header.search_blocks(row_id).collect();
+        for block in blocks {
+            let (_, offset) = self.header.jump_table[block];
+            self.seek(offset)?;
+
+            let start = block * BLOCK_SIZE;
+            let len = BLOCK_SIZE.min(self.len() - start);
+            for row_i in start..start + len {
+                let row = self.read_row(row_i)?;
+                if row.first().and_then(Value::as_i32) == Some(row_id) {
+                    return Ok(Some(row));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Iterator over all rows, decoding each row on demand
+    pub fn rows(&mut self) -> Rows<'_, R> {
+        Rows {
+            table: self,
+            index: 0,
+            failed: false,
+        }
+    }
+
+    fn seek(&mut self, offset: u32) -> Result<(), Error> {
+        self.offset = self.reader.seek(SeekFrom::Start(offset.into()))?;
+        Ok(())
+    }
+
+    fn read_row(&mut self, row_i: usize) -> Result<Row, Error> {
+        read_row(
+            &mut self.reader,
+            self.header.id,
+            &self.header.column_types,
+            row_i,
+            &mut self.offset,
+            false,
+        )
+    }
+}
+
+/// Decode the `row_i`-th row, `offset` is the position of the row and is advanced past it
+pub(crate) fn read_row<R>(
+    reader: &mut R,
+    table_id: u16,
+    column_types: &[u8],
+    row_i: usize,
+    offset: &mut u64,
+    lossless: bool,
+) -> Result<Row, Error>
+where
+    R: Read,
+{
+    // lossy strings are not as long as the bytes they're decoded from
+    let mut reader = CountingReader { reader, count: 0 };
+
+    let mut row = Vec::with_capacity(column_types.len());
+    for (column_i, t) in column_types.iter().enumerate() {
+        reader.count = 0;
+        let value = if lossless {
+            Value::read_lossless(*t, &mut reader)
+        } else {
+            Value::read(*t, &mut reader)
+        };
+
+        let value = value.map_err(|err| Error::Decode {
+            table_id,
+            row: row_i,
+            column: column_i,
+            offset: *offset,
+            source: Box::new(err),
+        })?;
+
+        *offset += reader.count;
+        row.push(value);
+    }
+
+    Ok(row)
+}
+
+/// Counts the bytes read through it
+struct CountingReader<'r, R> {
+    reader: &'r mut R,
+    count: u64,
+}
+
+impl<R> Read for CountingReader<'_, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+pub struct Rows<'a, R> {
+    table: &'a mut TableReader<R>,
+    index: usize,
+    // stop after the first error, position in the reader is unknown at that point
+    failed: bool,
+}
+
+impl<R> Iterator for Rows<'_, R>
+where
+    R: Read + Seek,
+{
+    type Item = Result<Row, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.index >= self.table.len() {
+            return None;
+        }
+
+        // seek at the start of every block, so reader can be shared between iterations
+        if self.index.is_multiple_of(BLOCK_SIZE) {
+            let (_, offset) = self.table.header.jump_table[self.index / BLOCK_SIZE];
+            if let Err(err) = self.table.seek(offset) {
+                self.failed = true;
+                return Some(Err(err));
+            }
+        }
+
+        let row = self.table.read_row(self.index);
+        self.index += 1;
+        self.failed = row.is_err();
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.table.len() - self.index;
+        (0, Some(remaining))
+    }
+}
+
+#[test]
+fn lookup() {
+    use crate::Table;
+
+    let mut table = Table::new(5000);
+    for i in 0..250 {
+        table
+            .add_row(vec![Value::I32(i * 2), Value::String(format!("row {}", i))])
+            .unwrap();
+    }
+
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+    let buffer = buffer.into_inner();
+
+    let mut reader = TableReader::from_bytes(&buffer).unwrap();
+    assert_eq!(reader.id(), 5000);
+    assert_eq!(reader.len(), 250);
+    assert_eq!(reader.column_types(), &[5, 11]);
+    assert_eq!(reader.jump_table().len(), 3);
+
+    assert_eq!(reader.row(300).unwrap().as_ref(), Some(&table.rows[150]));
+    assert_eq!(reader.row(0).unwrap().as_ref(), Some(&table.rows[0]));
+    assert_eq!(reader.row(498).unwrap().as_ref(), Some(&table.rows[249]));
+    assert_eq!(reader.row(301).unwrap(), None);
+    assert_eq!(reader.row(-1).unwrap(), None);
+
+    assert_eq!(reader.row_at(199).unwrap().as_ref(), Some(&table.rows[199]));
+    assert_eq!(reader.row_at(250).unwrap(), None);
+    assert_eq!(reader.block(2).unwrap(), &table.rows[200..]);
+
+    let rows: Result<Vec<Row>, Error> = reader.rows().collect();
+    assert_eq!(rows.unwrap(), table.rows);
+
+    // row out of order inside a block with ascending jump table ids
+    let mut unsorted = Table::new(5000);
+    for i in 0..200 {
+        let id = if i == 50 { 1000 } else { i };
+        unsorted
+            .add_row(vec![Value::I32(id), Value::String(format!("row {}", i))])
+            .unwrap();
+    }
+    let mut unsorted_buffer = io::Cursor::new(Vec::new());
+    unsorted.serialize(&mut unsorted_buffer).unwrap();
+    let mut unsorted_reader = TableReader::from_bytes(unsorted_buffer.get_ref()).unwrap();
+    assert_eq!(
+        unsorted_reader.row(1000).unwrap().as_ref(),
+        Some(&unsorted.rows[50])
+    );
+    assert_eq!(
+        unsorted_reader.row(150).unwrap().as_ref(),
+        Some(&unsorted.rows[150])
+    );
+    assert_eq!(unsorted_reader.row(50).unwrap(), None);
+
+    // unknown type of the second column
+    let mut broken = buffer.clone();
+    broken[8] = 12;
+    let mut reader = TableReader::from_bytes(&broken).unwrap();
+    let offset = reader.jump_table()[1].1;
+    let err = reader.row_at(100).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "failed to decode table 5000 at row 100, column 1, offset {:#x}",
+            offset + 4
+        )
+    );
+    let source = std::error::Error::source(&err).unwrap();
+    assert_eq!(source.to_string(), "unknown column type 12");
+}
@@ -0,0 +1,107 @@
+use std::{collections::HashMap, convert::TryFrom, hash::Hash, str::FromStr};
+
+use crate::{Error, Table, Value};
+
+/// Row of a [`crate::NamedTable`], with getters by column name
+#[derive(Debug, Clone, Copy)]
+pub struct RowRef<'a> {
+    table: &'a Table,
+    column_to_index: &'a HashMap<String, usize>,
+    index: usize,
+}
+
+impl<'a> RowRef<'a> {
+    pub(crate) fn new(
+        table: &'a Table,
+        column_to_index: &'a HashMap<String, usize>,
+        index: usize,
+    ) -> Self {
+        Self {
+            table,
+            column_to_index,
+            index,
+        }
+    }
+
+    fn column_index(&self, column_name: &str) -> Result<usize, Error> {
+        self.column_to_index
+            .get(column_name)
+            .copied()
+            .ok_or(Error::ColumnNotFound)
+    }
+
+    /// 0-based index of the row in the table
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn id(&self) -> i32 {
+        // PANIC row ids are checked on creation of the named table
+        self.values()[0].as_i32().unwrap()
+    }
+
+    pub fn values(&self) -> &'a [Value] {
+        &self.table.rows[self.index]
+    }
+
+    /// Raw value of the column
+    pub fn value(&self, column_name: &str) -> Option<&'a Value> {
+        let column_index = self.column_index(column_name).ok()?;
+        self.values().get(column_index)
+    }
+
+    pub fn get<T>(&self, column_name: &str) -> Result<T, Error>
+    where
+        T: TryFrom<&'a Value>,
+    {
+        self.table
+            .value(self.index, self.column_index(column_name)?)
+    }
+
+    /// Convert `"v,v,v"` string into `Vec<T>`
+    pub fn vector<T>(&self, column_name: &str, separator: &str) -> Result<Vec<T>, Error>
+    where
+        T: FromStr,
+    {
+        self.table
+            .vector(self.index, self.column_index(column_name)?, separator)
+    }
+
+    /// Same as [`RowRef::vector`], but the vector must have `length` items
+    pub fn array<T>(
+        &self,
+        column_name: &str,
+        separator: &str,
+        length: usize,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: FromStr,
+    {
+        let ret = self.vector(column_name, separator)?;
+
+        if ret.len() != length {
+            Err(Error::MismatchedLength)
+        } else {
+            Ok(ret)
+        }
+    }
+
+    /// Convert `"k:v,k:v"` string into `HashMap<K, V>`
+    pub fn map<K, V>(
+        &self,
+        column_name: &str,
+        pair_separator: &str,
+        kv_separator: &str,
+    ) -> Result<HashMap<K, V>, Error>
+    where
+        K: FromStr + Eq + Hash,
+        V: FromStr,
+    {
+        self.table.map(
+            self.index,
+            self.column_index(column_name)?,
+            pair_separator,
+            kv_separator,
+        )
+    }
+}
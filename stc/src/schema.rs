@@ -0,0 +1,75 @@
+use crate::{Error, Value};
+
+/// Column types of a table, and optionally their names
+///
+/// Empty schema means column types are not known yet, they are then taken from the first added row.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schema {
+    pub types: Vec<u8>,
+    pub names: Option<Vec<String>>,
+}
+
+impl Schema {
+    pub fn new(types: Vec<u8>) -> Self {
+        Self { types, names: None }
+    }
+
+    pub fn with_names(types: Vec<u8>, names: Vec<String>) -> Result<Self, Error> {
+        if types.len() != names.len() {
+            return Err(Error::InconsistentNamesAndTypesLength);
+        }
+
+        Ok(Self {
+            types,
+            names: Some(names),
+        })
+    }
+
+    /// Build the schema from type names, e.g. `["i32", "string"]`
+    pub fn from_type_names<S>(types: &[S]) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let types = types
+            .iter()
+            .map(|t| Value::type_from_name(t.as_ref()).ok_or(Error::InvalidColumnType))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self::new(types))
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    pub fn type_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.types
+            .iter()
+            .map(|t| Value::type_name(*t).unwrap_or("unknown"))
+    }
+
+    /// Check that row has as many columns as the schema, and that every value has the expected type
+    pub fn check_row(&self, row: &[Value]) -> Result<(), Error> {
+        if row.len() != self.types.len() {
+            return Err(Error::InconsistentRowLength);
+        }
+
+        for (column, (value, expected)) in row.iter().zip(self.types.iter()).enumerate() {
+            let found = value.type_as_u8();
+            if found != *expected {
+                return Err(Error::ColumnTypeMismatch {
+                    column,
+                    expected: *expected,
+                    found,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -3,30 +3,95 @@ use std::{
     convert::{TryFrom, TryInto},
     hash::Hash,
     io::{self, Read, Seek, SeekFrom},
-    ops::Deref,
     str::FromStr,
 };
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::WriteBytesExt;
+use indexmap::IndexMap;
+use json::JsonValue;
 
-use crate::{Error, Value};
+use crate::{reader::read_row, Error, RawString, Schema, TableHeader, TableWriter, Value};
 
 pub type Row = Vec<Value>;
 
+#[derive(Debug, Clone, Default)]
+pub struct DeserializeOptions {
+    /// Check every jump table entry against the row id and the offset it actually points at
+    pub strict: bool,
+    /// Keep strings that would change on re-serialization as [`Value::RawString`]
+    pub lossless: bool,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TableData"))]
 pub struct Table {
     pub id: u16,
+    pub schema: Schema,
     pub rows: Vec<Row>,
 }
 
+/// Deserialized [`Table`], before the rows are checked against the schema
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Table")]
+struct TableData {
+    id: u16,
+    schema: Schema,
+    rows: Vec<Row>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TableData> for Table {
+    type Error = Error;
+
+    fn try_from(data: TableData) -> Result<Self, Self::Error> {
+        let TableData { id, schema, rows } = data;
+
+        if let Some(t) = schema.types.iter().find(|t| Value::type_name(**t).is_none()) {
+            return Err(Error::UnknownColumnType(*t));
+        }
+        if let Some(names) = &schema.names {
+            if names.len() != schema.types.len() {
+                return Err(Error::InconsistentNamesAndTypesLength);
+            }
+        }
+
+        let mut table = Self::with_schema(id, schema);
+        for row in rows {
+            table.add_row(row)?;
+        }
+
+        Ok(table)
+    }
+}
+
 impl Table {
+    /// Create a table without a schema, it will be taken from the first added row
     pub fn new(id: u16) -> Self {
+        Self::with_schema(id, Schema::default())
+    }
+
+    pub fn with_schema(id: u16, schema: Schema) -> Self {
         Self {
             id,
+            schema,
             rows: Vec::new(),
         }
     }
 
+    /// Column types from the schema, or from the first row if the schema is empty
+    pub fn column_types(&self) -> Vec<u8> {
+        if !self.schema.is_empty() {
+            return self.schema.types.clone();
+        }
+
+        self.rows
+            .first()
+            .map(|row| row.iter().map(Value::type_as_u8).collect())
+            .unwrap_or_default()
+    }
+
     pub fn add_row(&mut self, row: Vec<Value>) -> Result<(), Error> {
         if self.rows.len() >= u16::MAX.into() {
             return Err(Error::TooManyRows);
@@ -42,12 +107,14 @@ impl Table {
             _ => return Err(Error::InvalidRowId),
         }
 
-        if let Some(first) = self.rows.first() {
-            if first.len() != row.len() {
-                return Err(Error::InconsistentRowLength);
-            }
+        // schema is not known, take it from the existing rows or this one
+        if self.schema.is_empty() {
+            let first = self.rows.first().unwrap_or(&row);
+            self.schema.types = first.iter().map(Value::type_as_u8).collect();
         }
 
+        self.schema.check_row(&row)?;
+
         self.rows.push(row);
 
         Ok(())
@@ -57,115 +124,126 @@ impl Table {
     where
         R: Read + Seek,
     {
-        let table_id = reader.read_u16::<LittleEndian>()?;
-        let last_block_size: u64 = reader.read_u16::<LittleEndian>()?.into(); // size of the last 65kb block
-        let rows = reader.read_u16::<LittleEndian>()?;
+        Self::deserialize_with(reader, &DeserializeOptions::default())
+    }
 
-        let mut table = Self::new(table_id);
+    pub fn deserialize_with<R>(reader: &mut R, options: &DeserializeOptions) -> Result<Self, Error>
+    where
+        R: Read + Seek,
+    {
+        let header = TableHeader::read(reader)?;
+
+        let mut table = Self::with_schema(header.id, Schema::new(header.column_types.clone()));
 
-        if rows == 0 {
+        if header.rows == 0 {
             return Ok(table);
         }
 
-        let columns: usize = reader.read_u8()?.into();
-        let mut column_types = Vec::with_capacity(columns);
-        for _ in 0..columns {
-            let t = reader.read_u8()?;
-            column_types.push(t);
+        if !options.strict {
+            // skip the rest of the table
+            let (_, first_row_offset) = header.jump_table[0];
+            reader.seek(SeekFrom::Start(first_row_offset.into()))?;
         }
 
-        // read jump table
-        let _first_row_id = reader.read_i32::<LittleEndian>()?;
-        let first_row_offset: u64 = reader.read_u32::<LittleEndian>()?.into();
-
-        // skip the rest of the table
-        reader.seek(SeekFrom::Start(first_row_offset))?;
-
-        for _ in 0..rows {
-            let mut row = Vec::with_capacity(columns);
+        let mut offset = reader.stream_position()?;
+
+        table.rows.reserve(header.rows.into());
+        for row_i in 0..usize::from(header.rows) {
+            let row_offset = offset;
+
+            let row = read_row(
+                reader,
+                header.id,
+                &header.column_types,
+                row_i,
+                &mut offset,
+                options.lossless,
+            )?;
+
+            if options.strict && row_i % 100 == 0 {
+                let entry = row_i / 100;
+                let (expected_id, expected_offset) = header.jump_table[entry];
+
+                let row_id = row
+                    .first()
+                    .and_then(Value::as_i32)
+                    .ok_or(Error::InvalidRowId)?;
+                if row_id != expected_id {
+                    return Err(Error::JumpTableRowIdMismatch {
+                        entry,
+                        expected: expected_id,
+                        found: row_id,
+                    });
+                }
 
-            for t in &column_types {
-                row.push(Value::read(*t, reader)?);
+                if row_offset != u64::from(expected_offset) {
+                    return Err(Error::JumpTableOffsetMismatch {
+                        entry,
+                        expected: expected_offset,
+                        found: row_offset,
+                    });
+                }
             }
 
             table.rows.push(row);
         }
 
-        let cur_pos = reader.seek(SeekFrom::Current(0))?;
-        if last_block_size != (cur_pos - 4) % 65536 {
+        let cur_pos = reader.stream_position()?;
+        if u64::from(header.last_block_size) != (cur_pos - 4) % 65536 {
             return Err(Error::LastBlockSizeMismatch);
         }
 
         Ok(table)
     }
 
-    pub fn serialize<W>(&self, writer: &mut W) -> Result<(), Error>
-    where
-        W: WriteBytesExt + Seek,
-    {
-        writer.write_u16::<LittleEndian>(self.id)?;
-
-        writer.write_u16::<LittleEndian>(2)?; // lbs placeholder, current position
-
-        let rows_n = self.rows.len().try_into().map_err(|_| Error::TooManyRows)?;
-
-        writer.write_u16::<LittleEndian>(rows_n)?;
-
-        if self.rows.is_empty() {
-            return Ok(());
-        }
-
-        // PANIC checked above
-        let first = self.rows.first().unwrap();
-
-        let columns_n: u8 = first.len().try_into().map_err(|_| Error::TooManyColumns)?;
-        writer.write_u8(columns_n)?;
-
-        // column types
-        for v in first.iter() {
-            writer.write_u8(v.type_as_u8())?;
-        }
-
-        // jump table placeholder
-        let jump_table_size = (self.rows.len() + 99) / 100; // rounding up division by 100
-        for _ in 0..jump_table_size {
-            writer.write_i32::<LittleEndian>(0)?; // id
-            writer.write_u32::<LittleEndian>(0)?; // offset
-        }
-
-        let mut jump_table = Vec::with_capacity(jump_table_size);
+    /// Locations of strings that differ from their lossy decoding, as `(row, column, string)`
+    pub fn raw_strings(&self) -> impl Iterator<Item = (usize, usize, &RawString)> {
+        self.rows.iter().enumerate().flat_map(|(row_i, row)| {
+            row.iter().enumerate().filter_map(move |(column_i, column)| match column {
+                Value::RawString(s) => Some((row_i, column_i, s)),
+                _ => None,
+            })
+        })
+    }
 
-        for (row_i, row) in self.rows.iter().enumerate() {
-            for (column_i, column) in row.iter().enumerate() {
-                if row_i % 100 == 0 && column_i == 0 {
-                    let id = column.as_i32().ok_or(Error::InvalidRowId)?;
-                    let pos: u32 = writer
-                        .seek(SeekFrom::Current(0))?
-                        .try_into()
-                        .map_err(|_| Error::BookmarkOutOfBounds)?;
+    /// Escape backslashes and new lines in strings, so that every row of a .csv stays on one line
+    pub fn escape_strings(&mut self) {
+        self.map_strings(escape);
+    }
 
-                    jump_table.push((id, pos));
-                }
+    /// Undo [`Table::escape_strings`]
+    pub fn unescape_strings(&mut self) {
+        self.map_strings(unescape);
+    }
 
-                column.serialize(writer)?;
+    fn map_strings(&mut self, f: fn(&str) -> String) {
+        for value in self.rows.iter_mut().flatten() {
+            if let Value::String(string) = value {
+                *string = f(string);
             }
         }
+    }
 
-        let lbs = (writer.seek(SeekFrom::Current(0))? - 4) % 65536;
-        writer.seek(SeekFrom::Start(2))?;
-        writer.write_u16::<LittleEndian>(lbs as u16)?;
+    /// Read only the header and the jump table, without decoding any rows
+    pub fn read_header<R>(reader: &mut R) -> Result<TableHeader, Error>
+    where
+        R: Read,
+    {
+        TableHeader::read(reader)
+    }
 
-        assert_eq!(jump_table.len(), jump_table_size);
+    pub fn serialize<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: WriteBytesExt + Seek,
+    {
+        let rows_n = self.rows.len().try_into().map_err(|_| Error::TooManyRows)?;
+        let schema = Schema::new(self.column_types());
 
-        // seek to the start of the jump table
-        // id (2), lbs (2), rows_n (2), columns_n (1), column_types (columns_n)
-        writer.seek(SeekFrom::Start(7 + u64::from(columns_n)))?;
-        for (id, offset) in jump_table {
-            writer.write_i32::<LittleEndian>(id)?;
-            writer.write_u32::<LittleEndian>(offset)?;
+        let mut writer = TableWriter::new(writer, self.id, schema, rows_n)?;
+        for row in self.rows.iter() {
+            writer.write_row(row)?;
         }
-
-        writer.flush()?;
+        writer.finish()?;
 
         Ok(())
     }
@@ -184,10 +262,7 @@ impl Table {
         reader.read_record(&mut types)?;
         let types: Vec<&str> = types.iter().collect();
 
-        let mut table = Self {
-            id,
-            rows: Vec::new(),
-        };
+        let mut table = Self::with_schema(id, Schema::from_type_names(&types)?);
         for (row_i, record) in reader.records().enumerate() {
             let row = record?;
             let row: Result<Vec<Value>, _> = row
@@ -195,7 +270,7 @@ impl Table {
                 .enumerate()
                 .map(|(col_i, col)| {
                     let col_type = types.get(col_i).ok_or(Error::InconsistentNamesAndTypesLength)?;
-                    match col_type.deref() {
+                    match *col_type {
                         "i8" => col
                             .parse()
                             .map(Value::I8)
@@ -253,22 +328,25 @@ impl Table {
     where
         W: io::Write,
     {
-        if self.rows.is_empty() {
+        let schema = Schema::new(self.column_types());
+        if schema.is_empty() {
             return Ok(writer);
         }
 
         let mut writer = csv::Writer::from_writer(writer);
 
-        let first = self.rows.first().unwrap(); // PANIC checked earlier
-
         if with_names {
-            let column_names = first.iter().enumerate().map(|(i, _)| format!("col-{}", i));
-            writer.write_record(column_names)?;
+            match &self.schema.names {
+                Some(names) => writer.write_record(names)?,
+                None => {
+                    let column_names = (0..schema.len()).map(|i| format!("col-{}", i));
+                    writer.write_record(column_names)?;
+                }
+            }
         }
 
         if with_types {
-            let column_types = first.iter().map(Value::type_as_string);
-            writer.write_record(column_types)?;
+            writer.write_record(schema.type_names())?;
         }
 
         for row in self.rows.iter() {
@@ -282,6 +360,66 @@ impl Table {
         Ok(writer)
     }
 
+    /// Convert the table into `{ "id": .., "types": [..], "rows": [[..], ..] }`,
+    /// `names` are included if the schema has them
+    pub fn to_json(&self) -> JsonValue {
+        let schema = Schema::new(self.column_types());
+
+        let mut object = JsonValue::new_object();
+        object["id"] = self.id.into();
+        if let Some(names) = &self.schema.names {
+            object["names"] = names.clone().into();
+        }
+        object["types"] = schema.type_names().collect::<Vec<_>>().into();
+        object["rows"] = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(Value::to_json).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+            .into();
+
+        object
+    }
+
+    /// Read the table from JSON produced by [`Table::to_json`]
+    pub fn from_json(value: &JsonValue) -> Result<Self, Error> {
+        let id = value["id"].as_u16().ok_or(Error::InvalidJson)?;
+
+        let types = json_strings(&value["types"])?;
+        let mut schema = Schema::from_type_names(&types)?;
+        if !value["names"].is_null() {
+            let names = json_strings(&value["names"])?.into_iter().map(String::from).collect();
+            schema = Schema::with_names(schema.types, names)?;
+        }
+
+        if !value["rows"].is_array() {
+            return Err(Error::InvalidJson);
+        }
+
+        let mut table = Self::with_schema(id, schema);
+        for (row_i, row) in value["rows"].members().enumerate() {
+            if !row.is_array() {
+                return Err(Error::InvalidJson);
+            }
+
+            if row.len() != table.schema.len() {
+                return Err(Error::InconsistentRowLength);
+            }
+
+            let row = row
+                .members()
+                .zip(table.schema.types.iter())
+                .enumerate()
+                .map(|(col_i, (col, t))| {
+                    Value::from_json(*t, col).ok_or(Error::ValueConversionFailed { table_id: id, row: row_i, column: col_i })
+                })
+                .collect::<Result<_, _>>()?;
+            table.add_row(row)?;
+        }
+
+        Ok(table)
+    }
+
     pub fn value<'a, T>(&'a self, row_i: usize, column_i: usize) -> Result<T, Error>
     where
         T: TryFrom<&'a Value>,
@@ -292,6 +430,20 @@ impl Table {
         T::try_from(column).map_err(|_| Error::ValueConversionFailed { table_id: self.id, row: row_i, column: column_i })
     }
 
+    /// Ids shared by several rows, in the order of their first row
+    pub fn duplicate_ids(&self) -> Vec<i32> {
+        let mut counts: IndexMap<i32, usize> = IndexMap::new();
+        for row_id in self.rows.iter().filter_map(|row| row.first().and_then(Value::as_i32)) {
+            *counts.entry(row_id).or_insert(0) += 1;
+        }
+
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(row_id, _)| row_id)
+            .collect()
+    }
+
     /// Convert `"v,v,v"` string into `Vec<T>`
     pub fn vector<T>(&self, row_i: usize, column_i: usize, separator: &str) -> Result<Vec<T>, Error>
     where
@@ -340,6 +492,55 @@ impl Table {
     }
 }
 
+/// Collect JSON array of strings
+pub(crate) fn json_strings(value: &JsonValue) -> Result<Vec<&str>, Error> {
+    if !value.is_array() {
+        return Err(Error::InvalidJson);
+    }
+
+    value
+        .members()
+        .map(|v| v.as_str().ok_or(Error::InvalidJson))
+        .collect()
+}
+
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\r' => escaped.push_str("\\r"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(string: &str) -> String {
+    let mut unescaped = String::with_capacity(string.len());
+    let mut chars = string.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('r') => unescaped.push('\r'),
+            Some('n') => unescaped.push('\n'),
+            // not produced by `escape`, keep as is
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
 #[test]
 fn adding() {
     use std::io;
@@ -380,6 +581,46 @@ fn adding() {
     ))
 }
 
+#[test]
+fn schema() {
+    // empty table keeps its columns
+    let schema = Schema::new(vec![5, 11]);
+    let table = Table::with_schema(1, schema.clone());
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+    assert_eq!(buffer.get_ref(), &[1, 0, 5, 0, 0, 0, 2, 5, 11]);
+
+    buffer.set_position(0);
+    let parsed = Table::deserialize(&mut buffer).unwrap();
+    assert_eq!(parsed.schema, schema);
+    assert!(parsed.rows.is_empty());
+
+    // mismatching types
+    let mut table = Table::with_schema(1, schema);
+    let err = table.add_row(vec![Value::I32(0), Value::U8(0)]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ColumnTypeMismatch { column: 1, expected: 11, found: 2 }
+    ));
+    assert_eq!(err.to_string(), "column 1 expects string, found u8");
+
+    // schema is taken from the first row
+    let mut table = Table::new(1);
+    table.add_row(vec![Value::I32(0), Value::I32(0)]).unwrap();
+    assert_eq!(table.schema, Schema::new(vec![5, 5]));
+    assert!(matches!(
+        table.add_row(vec![Value::I32(1), Value::U8(0)]),
+        Err(Error::ColumnTypeMismatch { column: 1, expected: 5, found: 2 })
+    ));
+
+    // rows added directly are checked on serialization
+    table.rows.push(vec![Value::I32(1), Value::U8(0)]);
+    assert!(matches!(
+        table.serialize(&mut io::Cursor::new(Vec::new())),
+        Err(Error::ColumnTypeMismatch { column: 1, .. })
+    ));
+}
+
 #[test]
 fn getters() {
     let mut table = Table::new(1);
@@ -434,6 +675,141 @@ fn getters() {
     }
 }
 
+#[test]
+fn strict() {
+    let mut table = Table::new(5000);
+    for i in 0..150 {
+        table
+            .add_row(vec![Value::I32(i), Value::String("a".repeat(i as usize))])
+            .unwrap();
+    }
+
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+    let buffer = buffer.into_inner();
+
+    let header = Table::read_header(&mut io::Cursor::new(&buffer)).unwrap();
+    assert_eq!(header.id, 5000);
+    assert_eq!(header.rows, 150);
+    assert_eq!(header.column_types, vec![5, 11]);
+    assert_eq!(header.jump_table.len(), 2);
+    assert_eq!(header.jump_table[0], (0, header.size() as u32));
+    assert_eq!(header.jump_table[1].0, 100);
+
+    let strict = DeserializeOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let parsed = Table::deserialize_with(&mut io::Cursor::new(&buffer), &strict).unwrap();
+    assert_eq!(parsed.rows, table.rows);
+
+    // invalid UTF-8 in the second row, lossy string is longer than the encoded one
+    let mut invalid_utf8 = buffer.clone();
+    invalid_utf8[header.size() as usize + 14] = 0xff;
+    let parsed = Table::deserialize_with(&mut io::Cursor::new(&invalid_utf8), &strict).unwrap();
+    assert_eq!(parsed.rows[1][1], Value::String("\u{fffd}".into()));
+    assert_eq!(parsed.rows[100], table.rows[100]);
+
+    // stale row id in the second entry
+    let mut stale_id = buffer.clone();
+    stale_id[17..21].copy_from_slice(&99i32.to_le_bytes());
+    assert!(Table::deserialize(&mut io::Cursor::new(&stale_id)).is_ok());
+    assert!(matches!(
+        Table::deserialize_with(&mut io::Cursor::new(&stale_id), &strict),
+        Err(Error::JumpTableRowIdMismatch { entry: 1, expected: 99, found: 100 })
+    ));
+
+    // second entry pointing at the wrong row
+    let mut stale_offset = buffer;
+    let offset = header.jump_table[1].1 + 1;
+    stale_offset[21..25].copy_from_slice(&offset.to_le_bytes());
+    assert!(Table::deserialize(&mut io::Cursor::new(&stale_offset)).is_ok());
+    assert!(matches!(
+        Table::deserialize_with(&mut io::Cursor::new(&stale_offset), &strict),
+        Err(Error::JumpTableOffsetMismatch { entry: 1, .. })
+    ));
+}
+
+#[test]
+fn lossless() {
+    let mut file = vec![
+        1, 0, 0, 0, 3, 0, // id, lbs, rows
+        2, 5, 11, // columns
+        0, 0, 0, 0, 17, 0, 0, 0, // jump table
+    ];
+    // valid ASCII
+    file.extend_from_slice(&[0, 0, 0, 0, 1, 2, 0, b'o', b'k']);
+    // ASCII flagged as non-ASCII
+    file.extend_from_slice(&[1, 0, 0, 0, 0, 2, 0, b'o', b'k']);
+    // invalid UTF-8
+    file.extend_from_slice(&[2, 0, 0, 0, 0, 2, 0, 0xff, b'!']);
+    let lbs = (file.len() as u16 - 4).to_le_bytes();
+    file[2..4].copy_from_slice(&lbs);
+
+    // lossy
+    let table = Table::deserialize(&mut io::Cursor::new(&file)).unwrap();
+    assert_eq!(table.rows[2][1], Value::String("\u{fffd}!".into()));
+    assert_eq!(table.raw_strings().count(), 0);
+
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+    assert_ne!(buffer.get_ref(), &file);
+
+    // lossless
+    let options = DeserializeOptions {
+        lossless: true,
+        ..Default::default()
+    };
+    let table = Table::deserialize_with(&mut io::Cursor::new(&file), &options).unwrap();
+    assert_eq!(table.rows[0][1], Value::String("ok".into()));
+
+    let raw: Vec<_> = table.raw_strings().collect();
+    assert_eq!(raw.len(), 2);
+    assert_eq!((raw[0].0, raw[0].1), (1, 1));
+    assert!(raw[0].2.is_utf8() && !raw[0].2.flag_matches());
+    assert_eq!((raw[1].0, raw[1].1), (2, 1));
+    assert!(!raw[1].2.is_utf8());
+
+    let mut buffer = io::Cursor::new(Vec::new());
+    table.serialize(&mut buffer).unwrap();
+    assert_eq!(buffer.get_ref(), &file);
+}
+
+#[test]
+fn json() {
+    let mut table = Table::new(5000);
+    table
+        .add_row(vec![
+            Value::I32(1),
+            Value::I8(-1),
+            Value::U64(u64::MAX),
+            Value::F32(0.1),
+            Value::String("a\nb".into()),
+        ])
+        .unwrap();
+
+    let json = table.to_json();
+    assert_eq!(
+        json.dump(),
+        r#"{"id":5000,"types":["i32","i8","u64","f32","string"],"rows":[[1,-1,18446744073709551615,0.10000000149011612,"a\nb"]]}"#
+    );
+
+    let parsed = Table::from_json(&json::parse(&json.dump()).unwrap()).unwrap();
+    assert_eq!(parsed.id, 5000);
+    assert_eq!(parsed.schema, table.schema);
+    assert_eq!(parsed.rows, table.rows);
+
+    // value out of range of the column type
+    let json = json::parse(r#"{"id":1,"types":["i32","i8"],"rows":[[1,128]]}"#).unwrap();
+    assert!(matches!(
+        Table::from_json(&json),
+        Err(Error::ValueConversionFailed { table_id: 1, row: 0, column: 1 })
+    ));
+
+    let json = json::parse(r#"{"id":1,"types":"i32","rows":[]}"#).unwrap();
+    assert!(matches!(Table::from_json(&json), Err(Error::InvalidJson)));
+}
+
 #[cfg(feature = "csv")]
 #[test]
 fn csv() {
@@ -452,4 +828,57 @@ fn csv() {
 
     let writer = table.to_csv(Vec::new(), false, true).unwrap();
     assert_eq!("i32\n101\n", String::from_utf8(writer).unwrap());
+
+    // types only
+    let table = Table::from_csv(5000, std::io::Cursor::new("i32,string\n")).unwrap();
+    assert_eq!(table.schema, Schema::new(vec![5, 11]));
+    assert!(table.rows.is_empty());
+
+    let writer = table.to_csv(Vec::new(), true, true).unwrap();
+    assert_eq!("col-0,col-1\ni32,string\n", String::from_utf8(writer).unwrap());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn csv_round_trip() {
+    use std::io::{BufRead, Cursor};
+
+    let mut table = Table::new(5000);
+    for (id, string) in [
+        "line\nbreak",
+        "crlf\r\n",
+        "literal \\n",
+        "backslash \\",
+        "\\\\r\\\n",
+        "",
+    ]
+    .iter()
+    .enumerate()
+    {
+        table
+            .add_row(vec![Value::I32(id as i32), Value::String(string.to_string())])
+            .unwrap();
+    }
+    let mut original = Cursor::new(Vec::new());
+    table.serialize(&mut original).unwrap();
+
+    // as stc2csv and csv2stc do it
+    let mut escaped = table.clone();
+    escaped.escape_strings();
+    assert_eq!(escaped.rows[2][1], Value::String("literal \\\\n".into()));
+    let csv = escaped.to_csv(Vec::new(), true, true).unwrap();
+    assert_eq!(String::from_utf8_lossy(&csv).lines().count(), 2 + table.rows.len());
+
+    let mut reader = Cursor::new(csv);
+    reader.read_line(&mut String::new()).unwrap();
+    let mut parsed = Table::from_csv(5000, reader).unwrap();
+    parsed.unescape_strings();
+    assert_eq!(parsed.rows, table.rows);
+
+    let mut converted = Cursor::new(Vec::new());
+    parsed.serialize(&mut converted).unwrap();
+    assert_eq!(converted.into_inner(), original.into_inner());
+
+    // unknown escapes are left alone
+    assert_eq!(unescape("\\t\\"), "\\t\\");
 }
@@ -0,0 +1,364 @@
+//! Checks for new data versions and downloads the `stc_data.dat` archive
+//!
+//! During login the client queries `Index/version`, and if `data_version` differs from the one it
+//! recorded, downloads the archive from the CDN.
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{Archive, Error};
+
+/// CDN the client downloads the archives from
+pub const CDN: &str = "http://dkn3dfwjnmzcj.cloudfront.net";
+
+/// URL of the archive for the data version, as `get_data_file_full_url` of the client builds it
+pub fn data_file_url(base: &str, version: &str) -> String {
+    format!(
+        "{}/data/stc_{}{:x}.zip",
+        base.trim_end_matches('/'),
+        version,
+        md5::compute(version)
+    )
+}
+
+/// `data_version` of the `Index/version` response
+pub fn parse_version(response: &str) -> Result<String, Error> {
+    let value = json::parse(response).map_err(|_| Error::InvalidJson)?;
+
+    match &value["data_version"] {
+        json::JsonValue::Number(_) => Ok(value["data_version"].dump()),
+        version => version
+            .as_str()
+            .map(ToOwned::to_owned)
+            .ok_or(Error::InvalidJson),
+    }
+}
+
+/// Performs the requests of [`Updater`]
+pub trait Transport {
+    /// Body of the response, non-2xx statuses are an error
+    fn get(&self, url: &str) -> Result<Vec<u8>, Error>;
+}
+
+/// Redirects [`HttpTransport`] follows for a single request
+pub const MAX_REDIRECTS: usize = 5;
+
+/// Plain HTTP/1.0 over a TCP connection, `https` is not supported
+///
+/// Redirects are followed, as long as they stay on `http`.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpTransport {
+    /// Timeout of connecting, and of every read and write, 30 seconds by default
+    pub timeout: Duration,
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Transport for HttpTransport {
+    fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        let mut url = url.to_owned();
+        for _ in 0..=MAX_REDIRECTS {
+            match self.request(&url)? {
+                Response::Body(body) => return Ok(body),
+                Response::Redirect(location) => url = location,
+            }
+        }
+
+        Err(Error::TooManyRedirects)
+    }
+}
+
+impl HttpTransport {
+    fn request(&self, url: &str) -> Result<Response, Error> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| Error::UnsupportedUrl(url.to_owned()))?;
+        let (host, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(Error::UnsupportedUrl(url.to_owned()));
+        }
+        let address = if host.contains(':') {
+            host.to_owned()
+        } else {
+            format!("{}:80", host)
+        };
+
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, host
+        );
+        let mut stream = self.connect(&address)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        match parse_response(response)? {
+            // relative to the host
+            Response::Redirect(location) if location.starts_with('/') => {
+                Ok(Response::Redirect(format!("http://{}{}", host, location)))
+            }
+            response => Ok(response),
+        }
+    }
+
+    /// Connect to the first address that accepts the connection
+    fn connect(&self, address: &str) -> Result<TcpStream, Error> {
+        let mut last_err = None;
+        for address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses"))
+            .into())
+    }
+}
+
+enum Response {
+    Body(Vec<u8>),
+    /// `Location` of the redirect
+    Redirect(String),
+}
+
+fn parse_response(mut response: Vec<u8>) -> Result<Response, Error> {
+    let head_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or(Error::InvalidHttpResponse)?;
+    let head =
+        std::str::from_utf8(&response[..head_end]).map_err(|_| Error::InvalidHttpResponse)?;
+
+    let mut lines = head.split("\r\n");
+    // HTTP/1.1 200 OK
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or(Error::InvalidHttpResponse)?;
+    let headers: Vec<_> = lines.filter_map(|line| line.split_once(':')).collect();
+    let header = |header: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(header))
+            .map(|(_, value)| value.trim())
+    };
+
+    if let 301 | 302 | 303 | 307 | 308 = status {
+        let location = header("location").ok_or(Error::InvalidHttpResponse)?;
+        return Ok(Response::Redirect(location.to_owned()));
+    }
+    if !(200..300).contains(&status) {
+        return Err(Error::HttpStatus(status));
+    }
+
+    let length = header("content-length")
+        .map(str::parse::<usize>)
+        .transpose()
+        .map_err(|_| Error::InvalidHttpResponse)?;
+
+    let mut body = response.split_off(head_end + 4);
+    if let Some(length) = length {
+        if body.len() < length {
+            return Err(Error::InvalidHttpResponse);
+        }
+        body.truncate(length);
+    }
+
+    Ok(Response::Body(body))
+}
+
+/// Queries the version endpoint and downloads the archives
+pub struct Updater<T> {
+    transport: T,
+    version_url: String,
+    data_base: String,
+}
+
+impl Updater<HttpTransport> {
+    /// `version_url` is the full URL of `Index/version` on the game server
+    pub fn new<S>(version_url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::with_transport(HttpTransport::default(), version_url)
+    }
+}
+
+impl<T> Updater<T>
+where
+    T: Transport,
+{
+    pub fn with_transport<S>(transport: T, version_url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            transport,
+            version_url: version_url.into(),
+            data_base: CDN.to_owned(),
+        }
+    }
+
+    /// Base URL to download the archives from, [`CDN`] by default
+    pub fn with_data_base<S>(mut self, base: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.data_base = base.into();
+        self
+    }
+
+    /// Current data version on the server
+    pub fn latest_version(&self) -> Result<String, Error> {
+        let response = self.transport.get(&self.version_url)?;
+        let response = std::str::from_utf8(&response).map_err(|_| Error::InvalidJson)?;
+
+        parse_version(response)
+    }
+
+    /// Version to update to, `None` if the locally recorded version is up-to-date
+    pub fn check(&self, local_version: Option<&str>) -> Result<Option<String>, Error> {
+        let latest = self.latest_version()?;
+
+        if local_version == Some(latest.as_str()) {
+            Ok(None)
+        } else {
+            Ok(Some(latest))
+        }
+    }
+
+    pub fn data_file_url(&self, version: &str) -> String {
+        data_file_url(&self.data_base, version)
+    }
+
+    /// Download the archive of the version, and check that it has tables in it
+    ///
+    /// Bytes to store as `stc_data.dat` are available through [`Archive::into_inner`].
+    pub fn download(&self, version: &str) -> Result<Archive<io::Cursor<Vec<u8>>>, Error> {
+        let bytes = self.transport.get(&self.data_file_url(version))?;
+        let archive = Archive::new(io::Cursor::new(bytes))?;
+
+        if archive.table_names().next().is_none() {
+            return Err(Error::NoTablesInArchive);
+        }
+
+        Ok(archive)
+    }
+}
+
+#[test]
+fn update() {
+    use std::{io::BufRead, net::TcpListener, thread};
+
+    assert_eq!(
+        data_file_url("http://localhost/", "abc"),
+        "http://localhost/data/stc_abc900150983cd24fb0d6963f7d28e17f72.zip"
+    );
+    assert_eq!(parse_version(r#"{"data_version":"abc"}"#).unwrap(), "abc");
+    assert_eq!(parse_version(r#"{"data_version":301}"#).unwrap(), "301");
+    assert!(matches!(parse_version("{}"), Err(Error::InvalidJson)));
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let archive = crate::archive::test_archive();
+
+    let server_base = base.clone();
+    let server = thread::spawn(move || {
+        let base = server_base;
+        for stream in listener.incoming().take(4 + 2 + 3 + MAX_REDIRECTS + 1) {
+            let mut stream = stream.unwrap();
+            let mut reader = io::BufReader::new(&stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            // rest of the headers
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            let (status, location, body): (_, _, &[u8]) = match request.split(' ').nth(1).unwrap() {
+                "/Index/version" => ("200 OK", None, br#"{"data_version":"abc"}"#),
+                "/data/stc_abc900150983cd24fb0d6963f7d28e17f72.zip" => ("200 OK", None, &archive),
+                "/moved" => (
+                    "301 Moved Permanently",
+                    Some(format!("{}/Index/version", base)),
+                    b"",
+                ),
+                "/found" => ("302 Found", Some("/moved".to_owned()), b""),
+                "/loop" => ("307 Temporary Redirect", Some("/loop".to_owned()), b""),
+                _ => ("404 Not Found", None, b"not found"),
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\n",
+                status,
+                body.len()
+            )
+            .unwrap();
+            if let Some(location) = location {
+                write!(stream, "Location: {}\r\n", location).unwrap();
+            }
+            stream.write_all(b"\r\n").unwrap();
+            stream.write_all(body).unwrap();
+        }
+    });
+
+    let updater = Updater::new(format!("{}/Index/version", base)).with_data_base(&base);
+    assert_eq!(updater.check(Some("abc")).unwrap(), None);
+    assert_eq!(updater.check(None).unwrap().as_deref(), Some("abc"));
+
+    let mut archive = updater.download("abc").unwrap();
+    assert_eq!(archive.read_table("5000.stc").unwrap().id, 5000);
+
+    assert!(matches!(
+        updater.download("def"),
+        Err(Error::HttpStatus(404))
+    ));
+
+    // absolute and relative redirects
+    let transport = HttpTransport::default();
+    let version = br#"{"data_version":"abc"}"#;
+    assert_eq!(transport.get(&format!("{}/moved", base)).unwrap(), version);
+    assert_eq!(transport.get(&format!("{}/found", base)).unwrap(), version);
+    assert!(matches!(
+        transport.get(&format!("{}/loop", base)),
+        Err(Error::TooManyRedirects)
+    ));
+    server.join().unwrap();
+
+    assert!(matches!(
+        transport.get("https://localhost/"),
+        Err(Error::UnsupportedUrl(_))
+    ));
+
+    // server that never responds
+    let stalled = TcpListener::bind("127.0.0.1:0").unwrap();
+    let transport = HttpTransport {
+        timeout: std::time::Duration::from_millis(100),
+    };
+    match transport.get(&format!("http://{}/", stalled.local_addr().unwrap())) {
+        Err(Error::Io(err)) => assert!(matches!(
+            err.kind(),
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+        )),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
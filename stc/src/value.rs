@@ -1,13 +1,15 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use std::{
+    borrow::Cow,
     convert::{TryFrom, TryInto},
-    io,
+    fmt,
 };
 
 use crate::Error;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     I8(i8),
     U8(u8),
@@ -20,6 +22,33 @@ pub enum Value {
     F32(f32),
     F64(f64),
     String(String),
+    /// String that can't be reproduced byte-exact from `Value::String`, only produced in lossless mode
+    RawString(RawString),
+}
+
+/// String exactly as it is stored in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawString {
+    /// `is_ascii` flag as it was read
+    pub is_ascii: u8,
+    pub bytes: Vec<u8>,
+}
+
+impl RawString {
+    pub fn is_utf8(&self) -> bool {
+        std::str::from_utf8(&self.bytes).is_ok()
+    }
+
+    /// Whether the flag matches the one that would be written for the lossy decoded string
+    pub fn flag_matches(&self) -> bool {
+        let lossy = String::from_utf8_lossy(&self.bytes);
+        self.is_ascii == lossy.is_ascii() as u8
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
 }
 
 macro_rules! impl_as {
@@ -27,7 +56,7 @@ macro_rules! impl_as {
         pub fn $name(&self) -> Option<$type> {
             match self {
                 $(
-                    Value::$item(v) => Some(<$type>::from(*v)),
+                    Self::$item(v) => Some(<$type>::from(*v)),
                 )+
                 _ => None,
             }
@@ -35,8 +64,26 @@ macro_rules! impl_as {
     };
 }
 
+pub(crate) use impl_as;
+
 impl Value {
-    pub fn read<R>(column_type: u8, reader: &mut R) -> io::Result<Value>
+    pub fn read<R>(column_type: u8, reader: &mut R) -> Result<Value, Error>
+    where
+        R: ReadBytesExt,
+    {
+        Self::read_impl(column_type, reader, false)
+    }
+
+    /// Same as [`Value::read`], but strings that would not survive the round trip
+    /// (invalid UTF-8 or unexpected `is_ascii` flag) are kept as [`Value::RawString`]
+    pub fn read_lossless<R>(column_type: u8, reader: &mut R) -> Result<Value, Error>
+    where
+        R: ReadBytesExt,
+    {
+        Self::read_impl(column_type, reader, true)
+    }
+
+    fn read_impl<R>(column_type: u8, reader: &mut R, lossless: bool) -> Result<Value, Error>
     where
         R: ReadBytesExt,
     {
@@ -52,23 +99,29 @@ impl Value {
             9 => Value::F32(reader.read_f32::<LittleEndian>()?),
             10 => Value::F64(reader.read_f64::<LittleEndian>()?),
             11 => {
-                // UTF-8 is compatible with ASCII, so we can ignore this,
-                // we could seek over it, but that would require io::Seek constraint on the reader
-                reader.read_u8()?; // step over `is_ascii` flag
+                // UTF-8 is compatible with ASCII, so the flag is only needed in lossless mode
+                let is_ascii = reader.read_u8()?;
 
                 let len = reader.read_u16::<LittleEndian>()?;
                 let mut buffer = vec![0; usize::from(len)];
                 reader.read_exact(&mut buffer)?;
 
-                let string = String::from_utf8_lossy(&buffer).to_string();
-                Value::String(string)
-            }
-            unknown => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("unknown value type {}", unknown),
-                ))
+                match String::from_utf8(buffer) {
+                    Ok(string) if !lossless || is_ascii == string.is_ascii() as u8 => {
+                        Value::String(string)
+                    }
+                    Ok(string) => Value::RawString(RawString {
+                        is_ascii,
+                        bytes: string.into_bytes(),
+                    }),
+                    Err(err) if lossless => Value::RawString(RawString {
+                        is_ascii,
+                        bytes: err.into_bytes(),
+                    }),
+                    Err(err) => Value::String(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+                }
             }
+            unknown => return Err(Error::UnknownColumnType(unknown)),
         };
 
         Ok(value)
@@ -98,11 +151,56 @@ impl Value {
 
                 writer.write_all(s.as_bytes())?;
             }
+            Value::RawString(s) => {
+                writer.write_u8(s.is_ascii)?;
+
+                let len: u16 = s.bytes.len().try_into().map_err(|_| Error::StringTooBig)?;
+                writer.write_u16::<LittleEndian>(len)?;
+
+                writer.write_all(&s.bytes)?;
+            }
         }
 
         Ok(())
     }
 
+    pub fn to_json(&self) -> json::JsonValue {
+        match self {
+            Value::I8(v) => (*v).into(),
+            Value::U8(v) => (*v).into(),
+            Value::I16(v) => (*v).into(),
+            Value::U16(v) => (*v).into(),
+            Value::I32(v) => (*v).into(),
+            Value::U32(v) => (*v).into(),
+            Value::I64(v) => (*v).into(),
+            Value::U64(v) => (*v).into(),
+            Value::F32(v) => (*v).into(),
+            Value::F64(v) => (*v).into(),
+            Value::String(v) => v.as_str().into(),
+            Value::RawString(v) => v.to_string_lossy().into_owned().into(),
+        }
+    }
+
+    /// Convert JSON value into the value of `column_type`
+    pub fn from_json(column_type: u8, value: &json::JsonValue) -> Option<Value> {
+        let value = match column_type {
+            1 => Value::I8(value.as_i8()?),
+            2 => Value::U8(value.as_u8()?),
+            3 => Value::I16(value.as_i16()?),
+            4 => Value::U16(value.as_u16()?),
+            5 => Value::I32(value.as_i32()?),
+            6 => Value::U32(value.as_u32()?),
+            7 => Value::I64(value.as_i64()?),
+            8 => Value::U64(value.as_u64()?),
+            9 => Value::F32(value.as_f32()?),
+            10 => Value::F64(value.as_f64()?),
+            11 => Value::String(value.as_str()?.to_owned()),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
     pub fn type_as_u8(&self) -> u8 {
         match self {
             Value::I8(_) => 1,
@@ -115,7 +213,7 @@ impl Value {
             Value::U64(_) => 8,
             Value::F32(_) => 9,
             Value::F64(_) => 10,
-            Value::String(_) => 11,
+            Value::String(_) | Value::RawString(_) => 11,
         }
     }
 
@@ -131,11 +229,50 @@ impl Value {
             Value::U64(_) => "u64",
             Value::F32(_) => "f32",
             Value::F64(_) => "f64",
-            Value::String(_) => "string",
+            Value::String(_) | Value::RawString(_) => "string",
         }
         .to_string()
     }
 
+    /// Name of the column type, as used in table definitions and .csv files
+    pub fn type_name(column_type: u8) -> Option<&'static str> {
+        let name = match column_type {
+            1 => "i8",
+            2 => "u8",
+            3 => "i16",
+            4 => "u16",
+            5 => "i32",
+            6 => "u32",
+            7 => "i64",
+            8 => "u64",
+            9 => "f32",
+            10 => "f64",
+            11 => "string",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    pub fn type_from_name(name: &str) -> Option<u8> {
+        let column_type = match name {
+            "i8" => 1,
+            "u8" => 2,
+            "i16" => 3,
+            "u16" => 4,
+            "i32" => 5,
+            "u32" => 6,
+            "i64" => 7,
+            "u64" => 8,
+            "f32" => 9,
+            "f64" => 10,
+            "string" => 11,
+            _ => return None,
+        };
+
+        Some(column_type)
+    }
+
     impl_as!(as_i8 -> i8, I8);
     impl_as!(as_i16 -> i16, I16, I8, U8);
     impl_as!(as_i32 -> i32, I32, I16, I8, U16, U8);
@@ -152,25 +289,27 @@ impl Value {
     pub fn as_str(&self) -> Option<&str> {
         match self {
             Value::String(v) => Some(v),
+            Value::RawString(v) => std::str::from_utf8(&v.bytes).ok(),
             _ => None,
         }
     }
 }
 
-impl ToString for Value {
-    fn to_string(&self) -> String {
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Value::I8(v) => v.to_string(),
-            Value::U8(v) => v.to_string(),
-            Value::I16(v) => v.to_string(),
-            Value::U16(v) => v.to_string(),
-            Value::I32(v) => v.to_string(),
-            Value::U32(v) => v.to_string(),
-            Value::I64(v) => v.to_string(),
-            Value::U64(v) => v.to_string(),
-            Value::F32(v) => v.to_string(),
-            Value::F64(v) => v.to_string(),
-            Value::String(v) => v.to_string(),
+            Value::I8(v) => v.fmt(f),
+            Value::U8(v) => v.fmt(f),
+            Value::I16(v) => v.fmt(f),
+            Value::U16(v) => v.fmt(f),
+            Value::I32(v) => v.fmt(f),
+            Value::U32(v) => v.fmt(f),
+            Value::I64(v) => v.fmt(f),
+            Value::U64(v) => v.fmt(f),
+            Value::F32(v) => v.fmt(f),
+            Value::F64(v) => v.fmt(f),
+            Value::String(v) => v.fmt(f),
+            Value::RawString(v) => v.to_string_lossy().fmt(f),
         }
     }
 }
@@ -0,0 +1,269 @@
+use std::{
+    convert::TryInto,
+    io::{Seek, SeekFrom, Write},
+};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::{reader::BLOCK_SIZE, Error, Schema, Value};
+
+/// Encodes rows and keeps track of the jump table, offsets are relative to the start of row data
+struct RowEncoder {
+    schema: Schema,
+    jump_table: Vec<(i32, u64)>,
+    rows: usize,
+    data_len: u64,
+    buffer: Vec<u8>,
+}
+
+impl RowEncoder {
+    fn new(schema: Schema) -> Result<Self, Error> {
+        if schema.len() > u8::MAX.into() {
+            return Err(Error::TooManyColumns);
+        }
+
+        Ok(Self {
+            schema,
+            jump_table: Vec::new(),
+            rows: 0,
+            data_len: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    fn encode<W>(&mut self, row: &[Value], writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        if self.rows >= u16::MAX.into() {
+            return Err(Error::TooManyRows);
+        }
+
+        let id = match row.first() {
+            Some(Value::I32(id)) => *id,
+            _ => return Err(Error::InvalidRowId),
+        };
+
+        self.schema.check_row(row)?;
+
+        if self.rows.is_multiple_of(BLOCK_SIZE) {
+            self.jump_table.push((id, self.data_len));
+        }
+
+        self.buffer.clear();
+        for column in row {
+            column.serialize(&mut self.buffer)?;
+        }
+        writer.write_all(&self.buffer)?;
+
+        self.rows += 1;
+        self.data_len += self.buffer.len() as u64;
+
+        Ok(())
+    }
+}
+
+// id (2), lbs (2), rows (2), columns (1), column types, jump table
+fn header_size(columns: usize, rows: usize) -> u64 {
+    if columns == 0 {
+        return 6;
+    }
+
+    7 + columns as u64 + 8 * rows.div_ceil(BLOCK_SIZE) as u64
+}
+
+fn write_header<W>(writer: &mut W, id: u16, rows: u16, encoder: &RowEncoder) -> Result<(), Error>
+where
+    W: Write,
+{
+    let header_size = header_size(encoder.schema.len(), rows.into());
+    let lbs = (header_size + encoder.data_len - 4) % 65536;
+
+    writer.write_u16::<LittleEndian>(id)?;
+    writer.write_u16::<LittleEndian>(lbs as u16)?;
+    writer.write_u16::<LittleEndian>(rows)?;
+
+    if encoder.schema.is_empty() {
+        return Ok(());
+    }
+
+    // PANIC checked on creation of the encoder
+    writer.write_u8(encoder.schema.len().try_into().unwrap())?;
+    for t in encoder.schema.types.iter() {
+        writer.write_u8(*t)?;
+    }
+
+    for (row_id, offset) in encoder.jump_table.iter() {
+        let offset: u32 = (header_size + offset)
+            .try_into()
+            .map_err(|_| Error::BookmarkOutOfBounds)?;
+        writer.write_i32::<LittleEndian>(*row_id)?;
+        writer.write_u32::<LittleEndian>(offset)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the table one row at a time, without keeping the rows in memory
+///
+/// The size of the jump table depends on the amount of rows, so it has to be known upfront.
+/// Header is written with placeholders and patched in [`TableWriter::finish`].
+pub struct TableWriter<W> {
+    writer: W,
+    // position of the table start in the writer
+    start: u64,
+    id: u16,
+    rows: u16,
+    encoder: RowEncoder,
+}
+
+impl<W> TableWriter<W>
+where
+    W: Write + Seek,
+{
+    pub fn new(mut writer: W, id: u16, schema: Schema, rows: u16) -> Result<Self, Error> {
+        let encoder = RowEncoder::new(schema)?;
+        let start = writer.stream_position()?;
+
+        // placeholder, jump table is written zeroed out
+        write_header(&mut writer, id, 0, &encoder)?;
+        if !encoder.schema.is_empty() {
+            for _ in 0..usize::from(rows).div_ceil(BLOCK_SIZE) {
+                writer.write_i32::<LittleEndian>(0)?; // id
+                writer.write_u32::<LittleEndian>(0)?; // offset
+            }
+        }
+
+        Ok(Self {
+            writer,
+            start,
+            id,
+            rows,
+            encoder,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &[Value]) -> Result<(), Error> {
+        if self.encoder.rows >= usize::from(self.rows) {
+            return Err(Error::TooManyRows);
+        }
+
+        self.encoder.encode(row, &mut self.writer)
+    }
+
+    /// Patch the header and return the inner writer, positioned at the end of the table
+    pub fn finish(mut self) -> Result<W, Error> {
+        if self.encoder.rows != usize::from(self.rows) {
+            return Err(Error::RowCountMismatch {
+                expected: self.rows.into(),
+                written: self.encoder.rows,
+            });
+        }
+
+        let end = self.writer.stream_position()?;
+
+        self.writer.seek(SeekFrom::Start(self.start))?;
+        write_header(&mut self.writer, self.id, self.rows, &self.encoder)?;
+
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Writes the table into a writer that can't seek, e.g. stdout or a zip entry
+///
+/// Jump table precedes the rows and its offsets depend on the encoded size of every row,
+/// so the rows are kept encoded in memory and written after the header in [`BufferedTableWriter::finish`].
+pub struct BufferedTableWriter<W> {
+    writer: W,
+    id: u16,
+    encoder: RowEncoder,
+    data: Vec<u8>,
+}
+
+impl<W> BufferedTableWriter<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W, id: u16, schema: Schema) -> Result<Self, Error> {
+        Ok(Self {
+            writer,
+            id,
+            encoder: RowEncoder::new(schema)?,
+            data: Vec::new(),
+        })
+    }
+
+    pub fn write_row(&mut self, row: &[Value]) -> Result<(), Error> {
+        self.encoder.encode(row, &mut self.data)
+    }
+
+    /// Write the header followed by the rows, and return the inner writer
+    pub fn finish(mut self) -> Result<W, Error> {
+        // PANIC encoder checks the row limit
+        let rows = self.encoder.rows.try_into().unwrap();
+
+        write_header(&mut self.writer, self.id, rows, &self.encoder)?;
+        self.writer.write_all(&self.data)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+#[test]
+fn writing() {
+    use crate::Table;
+    use std::io;
+
+    let mut table = Table::new(5000);
+    for i in 0..250 {
+        table
+            .add_row(vec![Value::I32(i), Value::String(i.to_string())])
+            .unwrap();
+    }
+
+    let mut expected = io::Cursor::new(Vec::new());
+    table.serialize(&mut expected).unwrap();
+    let expected = expected.into_inner();
+
+    let mut writer =
+        TableWriter::new(io::Cursor::new(Vec::new()), 5000, table.schema.clone(), 250).unwrap();
+    for row in table.rows.iter() {
+        writer.write_row(row).unwrap();
+    }
+    assert!(matches!(
+        writer.write_row(&table.rows[0]),
+        Err(Error::TooManyRows)
+    ));
+    let written = writer.finish().unwrap();
+    assert_eq!(written.position(), expected.len() as u64);
+    assert_eq!(written.into_inner(), expected);
+
+    let mut writer = BufferedTableWriter::new(Vec::new(), 5000, table.schema.clone()).unwrap();
+    for row in table.rows.iter() {
+        writer.write_row(row).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), expected);
+
+    // fewer rows than declared
+    let mut writer =
+        TableWriter::new(io::Cursor::new(Vec::new()), 5000, table.schema.clone(), 2).unwrap();
+    writer.write_row(&table.rows[0]).unwrap();
+    assert!(matches!(
+        writer.finish(),
+        Err(Error::RowCountMismatch {
+            expected: 2,
+            written: 1
+        })
+    ));
+
+    // type mismatch
+    let mut writer = BufferedTableWriter::new(Vec::new(), 5000, table.schema).unwrap();
+    assert!(matches!(
+        writer.write_row(&[Value::I32(0), Value::I32(0)]),
+        Err(Error::ColumnTypeMismatch { column: 1, .. })
+    ));
+}
@@ -0,0 +1,39 @@
+//! Generated code is checked in, so that it is compiled against the crate as part of the tests
+
+#[allow(dead_code)]
+mod tables {
+    include!("codegen/tables.rs");
+}
+
+#[test]
+fn generated_rust() {
+    let defs = stc::definitions::parse(include_str!("codegen/definitions.txt")).unwrap();
+    let code = stc::codegen::generate_rust(&defs).unwrap();
+
+    // to update, write `code` into tests/codegen/tables.rs
+    assert_eq!(code, include_str!("codegen/tables.rs"));
+}
+
+#[test]
+fn generated_loaders() {
+    use stc::{definitions, NamedTable, Table, Value};
+
+    let mut table = Table::new(tables::table_ids::EQUIP_INFO);
+    table
+        .add_row(vec![Value::I32(1), Value::U8(2), Value::U8(3)])
+        .unwrap();
+
+    let rows = tables::EquipInfo::from_table(&table).unwrap();
+    assert_eq!(
+        rows,
+        [tables::EquipInfo {
+            id: 1,
+            col_1: 2,
+            col_1_2: 3
+        }]
+    );
+
+    let defs = definitions::parse(include_str!("codegen/definitions.txt")).unwrap();
+    let named = NamedTable::from_definition(table, &defs[&5007]).unwrap();
+    assert_eq!(tables::EquipInfo::from_named_table(&named).unwrap(), rows);
+}
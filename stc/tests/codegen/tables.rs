@@ -0,0 +1,191 @@
+// Generated from table definitions, do not edit
+
+pub mod table_ids {
+    pub const GUN: u16 = 5005;
+    pub const GUN_2: u16 = 5006;
+    pub const EQUIP_INFO: u16 = 5007;
+    pub const TABLE_5008: u16 = 5008;
+    pub const GUN_2_2: u16 = 5009;
+}
+
+/// `gun`, table 5005
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gun {
+    pub id: i32,
+    pub name: String,
+    pub r#type: u8,
+    pub rank_display: f32,
+    pub type_2: i64,
+    pub col_5: u8,
+    pub self_: String,
+}
+
+impl Gun {
+    pub const TABLE_ID: u16 = 5005;
+
+    pub fn from_table(table: &stc::Table) -> Result<Vec<Self>, stc::Error> {
+        (0..table.rows.len())
+            .map(|row| {
+                Ok(Self {
+                    id: table.value(row, 0)?,
+                    name: table.value(row, 1)?,
+                    r#type: table.value(row, 2)?,
+                    rank_display: table.value(row, 3)?,
+                    type_2: table.value(row, 4)?,
+                    col_5: table.value(row, 5)?,
+                    self_: table.value(row, 6)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn from_named_table(table: &stc::NamedTable) -> Result<Vec<Self>, stc::Error> {
+        table
+            .row_ids()
+            .map(|&row_id| {
+                Ok(Self {
+                    id: table.value(row_id, "id")?,
+                    name: table.value(row_id, "name")?,
+                    r#type: table.value(row_id, "type")?,
+                    rank_display: table.value(row_id, "rankDisplay")?,
+                    type_2: table.value(row_id, "type")?,
+                    col_5: table.value(row_id, "")?,
+                    self_: table.value(row_id, "self")?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `Gun`, table 5006
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gun2 {
+    pub id: i32,
+    pub col_1: String,
+}
+
+impl Gun2 {
+    pub const TABLE_ID: u16 = 5006;
+
+    pub fn from_table(table: &stc::Table) -> Result<Vec<Self>, stc::Error> {
+        (0..table.rows.len())
+            .map(|row| {
+                Ok(Self {
+                    id: table.value(row, 0)?,
+                    col_1: table.value(row, 1)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn from_named_table(table: &stc::NamedTable) -> Result<Vec<Self>, stc::Error> {
+        table
+            .row_ids()
+            .map(|&row_id| {
+                Ok(Self {
+                    id: table.value(row_id, "id")?,
+                    col_1: table.value(row_id, "")?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `equip_info`, table 5007
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquipInfo {
+    pub id: i32,
+    pub col_1: u8,
+    pub col_1_2: u8,
+}
+
+impl EquipInfo {
+    pub const TABLE_ID: u16 = 5007;
+
+    pub fn from_table(table: &stc::Table) -> Result<Vec<Self>, stc::Error> {
+        (0..table.rows.len())
+            .map(|row| {
+                Ok(Self {
+                    id: table.value(row, 0)?,
+                    col_1: table.value(row, 1)?,
+                    col_1_2: table.value(row, 2)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn from_named_table(table: &stc::NamedTable) -> Result<Vec<Self>, stc::Error> {
+        table
+            .row_ids()
+            .map(|&row_id| {
+                Ok(Self {
+                    id: table.value(row_id, "id")?,
+                    col_1: table.value(row_id, "")?,
+                    col_1_2: table.value(row_id, "col_1")?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `-`, table 5008
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table5008 {
+    pub id: i32,
+}
+
+impl Table5008 {
+    pub const TABLE_ID: u16 = 5008;
+
+    pub fn from_table(table: &stc::Table) -> Result<Vec<Self>, stc::Error> {
+        (0..table.rows.len())
+            .map(|row| {
+                Ok(Self {
+                    id: table.value(row, 0)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn from_named_table(table: &stc::NamedTable) -> Result<Vec<Self>, stc::Error> {
+        table
+            .row_ids()
+            .map(|&row_id| {
+                Ok(Self {
+                    id: table.value(row_id, "id")?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `gun_2`, table 5009
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gun22 {
+    pub id: i32,
+}
+
+impl Gun22 {
+    pub const TABLE_ID: u16 = 5009;
+
+    pub fn from_table(table: &stc::Table) -> Result<Vec<Self>, stc::Error> {
+        (0..table.rows.len())
+            .map(|row| {
+                Ok(Self {
+                    id: table.value(row, 0)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn from_named_table(table: &stc::NamedTable) -> Result<Vec<Self>, stc::Error> {
+        table
+            .row_ids()
+            .map(|&row_id| {
+                Ok(Self {
+                    id: table.value(row_id, "id")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -28,15 +28,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut args = pico_args::Arguments::from_env();
     let delete = args.contains("--del");
     let defs_path: Option<String> = args.opt_value_from_str("--def")?;
+    let format: Option<String> = args.opt_value_from_str("--format")?;
+    let dts_path: Option<PathBuf> = args.opt_value_from_str("--dts")?;
     let files = args.finish();
-    if files.is_empty() {
-        println!("Usage: [--def path] [--del] files");
+    if files.is_empty() && dts_path.is_none() {
+        println!("Usage: [--def path] [--format csv|json] [--dts path] [--del] files");
         println!("Options:");
-        println!("    --def    Path to table definitions to pull column names from");
-        println!("    --del    Delete input file after processing");
+        println!("    --def       Path to table definitions to pull column names from");
+        println!("    --format    Output format, csv by default");
+        println!("    --dts       Write TypeScript declarations of the definitions to path");
+        println!("    --del       Delete input file after processing");
+        println!("Files are `.stc` tables or `.zip`/`.dat` archives, tables of an archive are");
+        println!("written into a directory named after it");
         return Ok(());
     }
 
+    let convert = match format.as_deref() {
+        None | Some("csv") => stc_to_csv,
+        Some("json") => stc_to_json,
+        Some(format) => {
+            println!("Unknown format: {}", format);
+            return Ok(());
+        }
+    };
+
     let defs = match defs_path {
         Some(path) => {
             let contents = std::fs::read_to_string(path).expect("failed to read definitions file");
@@ -45,6 +60,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         None => Default::default(),
     };
 
+    if let Some(path) = dts_path {
+        if defs.is_empty() {
+            println!("--dts requires table definitions, see --def");
+            return Ok(());
+        }
+
+        let declarations =
+            stc::codegen::generate_typescript(&defs).expect("failed to generate declarations");
+        fs::write(&path, declarations).expect("failed to write declarations");
+        colored_println(" Writing", Color::Green, path.display());
+    }
+
     for path in files.iter().map(PathBuf::from) {
         if !path.exists() || !path.is_file() {
             colored_println("Skipping", Color::Yellow, path.display());
@@ -52,7 +79,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         match path.extension().and_then(OsStr::to_str) {
-            Some("stc") => stc_to_csv(&path, &defs),
+            Some("stc") => {
+                let mut file = fs::File::open(&path).expect("failed to open stc file");
+                let table =
+                    stc::Table::deserialize(&mut file).expect("failed to deserialize stc table");
+                convert(table, &path, &defs)
+            }
+            Some("zip") | Some("dat") => convert_archive(convert, &path, &defs),
             _ => continue,
         }
 
@@ -65,14 +98,49 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn stc_to_csv<P>(in_path: P, defs: &definitions::TableDefinitions)
-where
-    P: AsRef<Path>,
-{
-    let in_path = in_path.as_ref();
-    let mut file = fs::File::open(&in_path).expect("failed to open stc file");
-    let mut table = stc::Table::deserialize(&mut file).expect("failed to deserialize stc table");
+/// Convert every table of the archive, as if it was extracted into a directory named after it
+fn convert_archive(
+    convert: fn(stc::Table, &Path, &definitions::TableDefinitions),
+    in_path: &Path,
+    defs: &definitions::TableDefinitions,
+) {
+    let mut archive = stc::Archive::open(in_path).expect("failed to open archive");
+
+    let out_dir = in_path.with_extension("");
+    fs::create_dir_all(&out_dir).expect("failed to create output directory");
+
+    let names: Vec<String> = archive.table_names().map(ToOwned::to_owned).collect();
+    for name in names {
+        // entries may be nested in directories
+        let file_name = Path::new(&name).file_name().unwrap_or_else(|| name.as_ref());
+        let table = archive
+            .read_table(&name)
+            .expect("failed to deserialize stc table");
+
+        convert(table, &out_dir.join(file_name), defs);
+    }
+}
 
+fn named_table(
+    table: stc::Table,
+    def: &definitions::TableDefinition,
+    in_path: &Path,
+) -> stc::NamedTable {
+    let named = stc::NamedTable::from_definition(table, def).expect("failed to create named table");
+
+    let report = named.definition_report();
+    if !report.is_empty() {
+        colored_println(
+            " Warning",
+            Color::Yellow,
+            format!("{}: {}", in_path.display(), report),
+        );
+    }
+
+    named
+}
+
+fn stc_to_csv(mut table: stc::Table, in_path: &Path, defs: &definitions::TableDefinitions) {
     let def = defs.get(&table.id);
 
     let out_path = match def {
@@ -83,29 +151,24 @@ where
     if table.rows.is_empty() {
         colored_println("   Empty", Color::Cyan, in_path.display());
 
-        // `to_csv` doesn't write headers if table is empty
-        if let Some(def) = def {
-            let mut out = csv::Writer::from_path(out_path).unwrap();
+        // `to_csv` doesn't write headers if the table has no column types
+        if table.schema.is_empty() {
+            if let Some(def) = def {
+                let mut out = csv::Writer::from_path(out_path).unwrap();
 
-            out.write_record(&def.columns).expect("failed to write column names");
-            out.write_record(&def.types).expect("failed to write column types");
-            out.flush().expect("failed to flush");
-        }
+                out.write_record(&def.columns).expect("failed to write column names");
+                out.write_record(&def.types).expect("failed to write column types");
+                out.flush().expect("failed to flush");
+            }
 
-        return;
+            return;
+        }
+    } else {
+        colored_println(" Parsing", Color::Green, in_path.display());
     }
 
-    colored_println(" Parsing", Color::Green, in_path.display());
-
     // escape new lines
-    for row in table.rows.iter_mut() {
-        for col in row.iter_mut() {
-            if let stc::Value::String(string) = col {
-                let escaped = string.replace('\r', "\\r").replace('\n', "\\n");
-                *string = escaped;
-            }
-        }
-    }
+    table.escape_strings();
 
     let out = fs::OpenOptions::new()
         .create(true)
@@ -115,10 +178,45 @@ where
         .expect("failed to open file for writing");
 
     match def {
-        Some(def) => stc::NamedTable::from_definition(table, def)
-            .expect("failed to create named table")
-            .to_csv(out, true, true),
+        Some(def) => named_table(table, def, in_path).to_csv(out, true, true),
         None => table.to_csv(out, true, true),
     }
     .expect("failed to convert to csv");
 }
+
+fn stc_to_json(table: stc::Table, in_path: &Path, defs: &definitions::TableDefinitions) {
+    let def = defs.get(&table.id);
+
+    let out_path = match def {
+        Some(def) => in_path.with_file_name(format!("{}_{}.json", table.id, def.name)),
+        None => in_path.with_extension("json"),
+    };
+
+    if table.rows.is_empty() {
+        colored_println("   Empty", Color::Cyan, in_path.display());
+    } else {
+        colored_println(" Parsing", Color::Green, in_path.display());
+    }
+
+    let json = match def {
+        Some(def) => {
+            let named = named_table(table, def, in_path);
+            // rows keyed by id would lose the duplicates
+            named.to_json().unwrap_or_else(|err| {
+                colored_println(
+                    " Warning",
+                    Color::Yellow,
+                    format!(
+                        "{}: {}, writing rows without column names",
+                        in_path.display(),
+                        err
+                    ),
+                );
+                named.into_table().to_json()
+            })
+        }
+        None => table.to_json(),
+    };
+
+    fs::write(out_path, json::stringify_pretty(json, 2)).expect("failed to write json");
+}